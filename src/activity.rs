@@ -0,0 +1,115 @@
+use crate::{
+    data_handler::{Activity, ActivityState},
+    keymap::{self, Action},
+    theme::{self, Slot},
+    ui::centered_rect,
+};
+use std::time::Duration;
+use tui::{
+    backend::Backend,
+    layout::Constraint,
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Row, Table},
+    Frame,
+};
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// one frame of the spinner per redraw, while something is going on
+pub(crate) fn status_line(activity: &[Activity], tick: usize) -> String {
+    let running = activity
+        .iter()
+        .filter(|a| !matches!(a.state, ActivityState::Queued))
+        .count();
+    let queued = activity.len() - running;
+    match (running, queued) {
+        (0, 0) => "idle".to_owned(),
+        (running, 0) => format!("{} {running} running", SPINNER[tick % SPINNER.len()]),
+        (running, queued) => format!(
+            "{} {running} running, {queued} queued",
+            SPINNER[tick % SPINNER.len()]
+        ),
+    }
+}
+
+/// popup listing the requests in flight and waiting for a worker
+#[derive(Default)]
+pub(crate) struct ActivityPanel {
+    open: bool,
+}
+
+impl ActivityPanel {
+    pub(crate) fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub(crate) fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub(crate) fn handle(&mut self, action: Action) {
+        if matches!(action, Action::Cancel | Action::Back | Action::Activity) {
+            self.open = false;
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(&self, frame: &mut Frame<B>, activity: &[Activity]) {
+        let area = centered_rect(80, 50, frame.size());
+        let rows: Vec<Row> = activity
+            .iter()
+            .map(|a| {
+                let (state, color) = match a.state {
+                    ActivityState::Queued => ("queued".to_owned(), theme::color(Slot::Muted)),
+                    ActivityState::Running => ("running".to_owned(), theme::color(Slot::Ok)),
+                    ActivityState::Revalidating => {
+                        ("revalidating".to_owned(), theme::color(Slot::Info))
+                    }
+                    ActivityState::Retrying(attempt) => {
+                        (format!("retrying ({attempt})"), theme::color(Slot::Warning))
+                    }
+                };
+                Row::new(vec![
+                    Cell::from(format!("{:?}", a.view_id)),
+                    Cell::from(elapsed_str(a.since.elapsed())),
+                    Cell::from(state).style(Style::default().fg(color)),
+                    Cell::from(a.req.to_string()),
+                ])
+            })
+            .collect();
+        let mut title = format!(
+            "activity : [close: {}]",
+            keymap::keys(&[Action::Cancel, Action::Back, Action::Activity])
+        );
+        if rows.is_empty() {
+            title.push_str(" (idle)");
+        }
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["view", "elapsed", "state", "request"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .widths(&[
+                Constraint::Length(14),
+                Constraint::Length(8),
+                Constraint::Length(14),
+                Constraint::Percentage(100),
+            ])
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            );
+        frame.render_widget(Clear, area);
+        frame.render_widget(table, area);
+    }
+}
+
+fn elapsed_str(d: Duration) -> String {
+    match d.as_secs() {
+        0 => format!("{}ms", d.as_millis()),
+        s if s < 60 => format!("{:.1}s", d.as_secs_f64()),
+        s => format!("{}m{:02}s", s / 60, s % 60),
+    }
+}
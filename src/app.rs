@@ -0,0 +1,585 @@
+use crate::{
+    activity::ActivityPanel,
+    cache_stats::{CacheOutcome, CachePopup},
+    commit::{CommitDialog, CommitOutcome, CommitState},
+    config::LayoutConfig,
+    data_handler::*,
+    errors::{ErrorLog, ErrorOutcome, FailedRequest},
+    event::AppEvent,
+    finder::{Finder, FinderOutcome},
+    keymap::{self, Action, Keymap},
+    lister::*,
+    log_browser::{LogBrowser, LogBrowserOutcome},
+    login::{LoginOutcome, LoginPrompt},
+    panes::{
+        self, log_browser_help, CommitMessagePane, InfoPane, ListPane, MessagesPane, ParentPane,
+        TextPane,
+    },
+    status::{StatusOutcome, StatusView},
+    theme::{self, Slot},
+    ui::{self, Context, FocusManager, PaneId, UiFrame},
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use log::debug;
+use std::sync::{mpsc::Sender, Arc};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+
+const QUIT_PROMPT: &str = "quit svn-tui? [yes: 'y'/Enter], [no: any other key]";
+
+/// state of the ui; owned by the ui thread and changed only in reaction to an `AppEvent`
+pub(crate) struct App {
+    ctx: Context,
+    panes: FocusManager,
+    keymap: Keymap,
+    /// responses of the data handler are sent back through here
+    events: Sender<AppEvent>,
+    base_url: String,
+    working_copy: Option<String>,
+    login_prompt: Option<LoginPrompt>,
+    cache_popup: Option<CachePopup>,
+    activity_panel: ActivityPanel,
+    log_browser: Option<LogBrowser>,
+    status_view: Option<StatusView>,
+    commit_dialog: Option<CommitDialog>,
+    /// kept while closed, so a listing that is still running isn't started again
+    finder: Option<Finder>,
+    finder_open: bool,
+    /// requests were in flight on the last tick
+    busy: bool,
+    /// waiting for the quit to be confirmed
+    quitting: bool,
+}
+
+impl App {
+    pub(crate) fn new(
+        data_handler: Arc<DataHandler>,
+        keymap: Keymap,
+        layout: LayoutConfig,
+        events: Sender<AppEvent>,
+        base_url: String,
+        working_copy: Option<String>,
+        login_prompt: Option<LoginPrompt>,
+    ) -> Result<Self, CustomError> {
+        let lists = CustomLists::from(vec![CustomList::from(base_url.clone())]);
+        let CustomListsToDisplay { cur, .. } = lists.get_current();
+        let list_state = CustomListState::from(cur.ok_or(CustomError::NoDataToList)?);
+        let ctx = Context {
+            data_handler,
+            message: format!("requesting svn list for '{base_url}'"),
+            error_log: ErrorLog::default(),
+            lists,
+            list_state,
+            new_data_request: Some((
+                DataRequest::List(TargetUrl(base_url.clone())),
+                ViewId::MainList,
+            )),
+            open_log_browser: None,
+            focus: None,
+            tick: 0,
+        };
+        let panes = FocusManager::new(
+            vec![
+                Box::new(MessagesPane),
+                Box::new(ParentPane::new(2)),
+                Box::new(ParentPane::new(1)),
+                Box::new(ListPane::new()),
+                Box::new(TextPane::new()),
+                Box::new(InfoPane),
+                Box::new(CommitMessagePane::new()),
+            ],
+            PaneId::List,
+            layout,
+        );
+        Ok(Self {
+            ctx,
+            panes,
+            keymap,
+            events,
+            base_url,
+            working_copy,
+            login_prompt,
+            cache_popup: None,
+            activity_panel: ActivityPanel::default(),
+            log_browser: None,
+            status_view: None,
+            commit_dialog: None,
+            finder: None,
+            finder_open: false,
+            busy: false,
+            quitting: false,
+        })
+    }
+
+    /// advances the spinner; true if the screen has to be drawn again
+    pub(crate) fn on_tick(&mut self) -> bool {
+        let busy = !self.ctx.data_handler.activity().is_empty();
+        // one more draw once idle, to stop the spinner
+        let redraw = busy || self.busy;
+        self.busy = busy;
+        if busy {
+            self.ctx.tick = self.ctx.tick.wrapping_add(1);
+        }
+        redraw
+    }
+
+    /// false once the user wants to quit
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let KeyEvent {
+            code, modifiers, ..
+        } = key;
+        let ctx = &mut self.ctx;
+        ctx.error_log.dismiss();
+        if self.quitting {
+            self.quitting = false;
+            return !matches!(code, KeyCode::Char('y') | KeyCode::Enter);
+        }
+        // text fields take keys as they are
+        if let Some(prompt) = &mut self.login_prompt {
+            match prompt.handle(code) {
+                LoginOutcome::Editing => {}
+                LoginOutcome::Submit(login) => {
+                    ctx.message = format!("logged in as '{}'", login.username);
+                    ctx.data_handler.set_login(Some(login));
+                    ctx.data_handler.open_disk_cache(&self.base_url);
+                    self.login_prompt = None;
+                }
+                LoginOutcome::Cancel => {
+                    ctx.message = "login cancelled, using svn's own auth cache".to_owned();
+                    self.login_prompt = None;
+                }
+            }
+        } else if let Some(dialog) = &mut self.commit_dialog {
+            match dialog.handle(code, modifiers) {
+                CommitOutcome::Editing => {}
+                CommitOutcome::Cancel => {
+                    self.commit_dialog = None;
+                    ctx.message = "commit cancelled".to_owned();
+                }
+                CommitOutcome::Abort => {
+                    ctx.data_handler.cancel(ViewId::Commit);
+                    ctx.message = "commit stopped, it may have gone through in part".to_owned();
+                }
+                CommitOutcome::Submit(paths, msg) => {
+                    ctx.message = format!("committing {} path(s)", paths.len());
+                    let deleted_dirs = self
+                        .status_view
+                        .as_ref()
+                        .map_or_else(Vec::new, |status| status.deleted_directories(&paths));
+                    ctx.new_data_request = Some((
+                        DataRequest::Commit(paths, msg, deleted_dirs),
+                        ViewId::Commit,
+                    ));
+                }
+                CommitOutcome::ScrollPreview(up) => {
+                    let page = if up { Action::PageUp } else { Action::PageDown };
+                    self.panes.send_to(PaneId::Text, page, ctx);
+                }
+            }
+        } else if let (true, Some(finder)) = (self.finder_open, &mut self.finder) {
+            let (path, open) = match finder.handle(code, modifiers) {
+                FinderOutcome::Searching => return true,
+                FinderOutcome::Close => {
+                    self.finder_open = false;
+                    ctx.message.clear();
+                    return true;
+                }
+                FinderOutcome::GoTo(path) => (path, false),
+                FinderOutcome::Open(path) => (path, true),
+            };
+            let (lists, selected) = finder.lists_to(&path);
+            self.finder_open = false;
+            ctx.message = format!("found '{path}'");
+            ctx.go_to(lists, selected);
+            if open {
+                self.panes.send_to(PaneId::List, Action::Open, ctx);
+            }
+        } else if !self.panes.key(key, ctx) {
+            // the search line of the list takes keys as they are, too
+            if let Some(action) = self.keymap.resolve(key) {
+                self.handle_action(action);
+            }
+        }
+        true
+    }
+
+    fn handle_action(&mut self, action: Action) {
+        let ctx = &mut self.ctx;
+        if ctx.error_log.is_open() {
+            match ctx.error_log.handle(action) {
+                ErrorOutcome::Browsing | ErrorOutcome::Close => {}
+                ErrorOutcome::Retry(req, view_id) => {
+                    ctx.message = format!("retrying {req}");
+                    ctx.new_data_request = Some((req, view_id));
+                }
+            }
+        } else if let Some(popup) = &mut self.cache_popup {
+            let prefix = match popup.handle(action) {
+                CacheOutcome::Browsing => None,
+                CacheOutcome::Close => {
+                    self.cache_popup = None;
+                    None
+                }
+                CacheOutcome::ClearSubtree => Some(Some(popup.subtree().to_owned())),
+                CacheOutcome::ClearAll => Some(None),
+            };
+            if let (Some(prefix), Some(popup)) = (prefix, &mut self.cache_popup) {
+                let (in_memory, on_disk) = ctx.data_handler.clear_cache(prefix.as_deref());
+                ctx.message = format!(
+                    "removed {in_memory} cached entries of {} ({on_disk} on disk)",
+                    prefix.as_deref().unwrap_or("all urls")
+                );
+                popup.set_stats(ctx.data_handler.cache_stats());
+            }
+        } else if self.activity_panel.is_open() {
+            self.activity_panel.handle(action);
+        } else if action == Action::Retry {
+            match ctx.error_log.last_retryable() {
+                Some((req, view_id)) => {
+                    ctx.message = format!("retrying {req}");
+                    ctx.new_data_request = Some((req, view_id));
+                }
+                None => ctx.message = "nothing to retry".to_owned(),
+            }
+        } else if action == Action::Errors {
+            ctx.error_log.open();
+        } else if action == Action::Activity {
+            self.activity_panel.open();
+        } else if panes::diff_action(action).is_some() {
+            // diffs opened from the log browser or the status view are stepped through from there
+            self.panes.send_to(PaneId::Text, action, ctx);
+        } else if let Some(browser) = &mut self.log_browser {
+            match browser.handle(action) {
+                LogBrowserOutcome::Browsing => {}
+                LogBrowserOutcome::Close => {
+                    self.log_browser = None;
+                    ctx.message.clear();
+                }
+                LogBrowserOutcome::Diff(range) => {
+                    let url = browser.url().to_owned();
+                    ctx.message = format!("requesting {range} for '{url}'");
+                    ctx.new_data_request = Some((
+                        DataRequest::Diff(TargetUrl(url), range),
+                        ViewId::RightInfoPane,
+                    ));
+                }
+            }
+        } else if let Some(status) = &mut self.status_view {
+            match status.handle(action) {
+                StatusOutcome::Browsing => {}
+                StatusOutcome::Close => {
+                    self.status_view = None;
+                    ctx.message.clear();
+                }
+                StatusOutcome::Refresh(incoming) => {
+                    ctx.new_data_request = Some((
+                        DataRequest::Status(TargetUrl(status.wc().to_owned()), incoming),
+                        ViewId::Status,
+                    ));
+                }
+                StatusOutcome::Diff(path, range) => {
+                    ctx.message = format!("requesting {range} for '{path}'");
+                    ctx.new_data_request = Some((
+                        DataRequest::Diff(TargetUrl(path), range),
+                        ViewId::RightInfoPane,
+                    ));
+                }
+                StatusOutcome::Message(reason) => ctx.message = reason,
+                StatusOutcome::Commit(paths) => {
+                    ctx.message =
+                        format!("requesting the combined diff of {} path(s)", paths.len());
+                    ctx.new_data_request =
+                        Some((DataRequest::BaseDiff(paths.clone()), ViewId::RightInfoPane));
+                    self.commit_dialog = Some(CommitDialog::new(paths));
+                }
+            }
+        } else if !self.panes.route(action, ctx) {
+            self.handle_global_action(action);
+        }
+    }
+
+    /// actions of the main screen that no pane took
+    fn handle_global_action(&mut self, action: Action) {
+        let ctx = &mut self.ctx;
+        match action {
+            Action::Quit => self.quitting = true,
+            // stops whatever svn is still doing; quitting is only offered once nothing runs
+            Action::Cancel => match ctx.data_handler.cancel_all() {
+                0 => self.quitting = true,
+                n => {
+                    ctx.message = format!("cancelled {n} request(s)");
+                    // a cancelled request gets no answer, the finder would wait for it for good
+                    if let Some(finder) = &mut self.finder {
+                        finder.stop_listing();
+                    }
+                }
+            },
+            Action::Cache => {
+                let subtree = match ctx.lists.get_current() {
+                    CustomListsToDisplay {
+                        cur: Some(custom_list),
+                        ..
+                    } => custom_list.base_url,
+                    _ => self.base_url.clone(),
+                };
+                self.cache_popup = Some(CachePopup::new(ctx.data_handler.cache_stats(), subtree));
+            }
+            Action::Status => match &self.working_copy {
+                Some(wc) => {
+                    ctx.message = status_help(wc);
+                    self.status_view = Some(StatusView::new(wc.clone()));
+                    ctx.new_data_request = Some((
+                        DataRequest::Status(TargetUrl(wc.clone()), false),
+                        ViewId::Status,
+                    ));
+                }
+                None => {
+                    ctx.message =
+                        "status needs svn-tui to be started from a working copy".to_owned();
+                }
+            },
+            Action::Find => {
+                let url = match ctx.lists.get_current() {
+                    CustomListsToDisplay {
+                        cur: Some(custom_list),
+                        ..
+                    } => custom_list.base_url,
+                    _ => self.base_url.clone(),
+                };
+                // the last search of the same directory is picked up where it was left
+                let finder = match &mut self.finder {
+                    Some(finder) if finder.url() == url => finder,
+                    finder => finder.insert(Finder::new(url.clone())),
+                };
+                // a finished listing is asked for again, the cache answers it or sees it's stale
+                if !finder.listing() {
+                    finder.start_listing();
+                    ctx.message = format!("listing everything below '{url}'");
+                    ctx.new_data_request =
+                        Some((DataRequest::Tree(TargetUrl(url)), ViewId::Finder));
+                }
+                self.finder_open = true;
+            }
+            Action::Log => {
+                if let CustomListsToDisplay {
+                    cur: Some(custom_list),
+                    ..
+                } = ctx.lists.get_current()
+                {
+                    let mut url = custom_list.base_url.clone();
+                    if let Some(selected) = custom_list.get_current_selected(&ctx.list_state) {
+                        url.push_str(&selected.name);
+                    }
+                    ctx.message = log_browser_help(&url);
+                    self.log_browser = Some(LogBrowser::new(url, None));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn on_response(
+        &mut self,
+        req: DataRequest,
+        view_id: ViewId,
+        res_resp: ResultDataResponse,
+    ) {
+        debug!("data received");
+        let ctx = &mut self.ctx;
+        // only the list cares whether a response refreshes one shown before
+        let res_resp = match res_resp {
+            Ok(DataResponse::Refreshed(response))
+                if !matches!(*response, DataResponse::List(_)) =>
+            {
+                Ok(*response)
+            }
+            res_resp => res_resp,
+        };
+        match res_resp {
+            Ok(DataResponse::Log(log)) if view_id == ViewId::LogBrowser => {
+                if let Some(browser) = &mut self.log_browser {
+                    if String::from(TargetUrl::from(req)) == browser.url() {
+                        browser.append(log);
+                    }
+                }
+            }
+            Ok(DataResponse::Text(output)) if view_id == ViewId::Commit => {
+                let summary = output
+                    .lines()
+                    .rev()
+                    .find(|l| !l.trim().is_empty())
+                    .unwrap_or("commit done")
+                    .to_owned();
+                if let Some(dialog) = &mut self.commit_dialog {
+                    dialog.state = CommitState::Done(summary);
+                }
+            }
+            Ok(DataResponse::Status(entries)) => {
+                if let (Some(status), DataRequest::Status(_, incoming)) =
+                    (&mut self.status_view, &req)
+                {
+                    status.set_entries(entries, *incoming);
+                }
+            }
+            Ok(response @ (DataResponse::Tree(_) | DataResponse::TreePart(..))) => {
+                let url = String::from(TargetUrl::from(req));
+                if let Some(finder) = self.finder.as_mut().filter(|f| f.url() == url) {
+                    finder.receive(response);
+                }
+            }
+            Ok(response) => {
+                if !self.panes.respond(view_id, req, response, ctx) {
+                    debug!("no pane shows {view_id:?}");
+                }
+            }
+            Err(e) if e.is_auth() => {
+                ctx.message = "svn authentication failed, please log in".to_owned();
+                self.login_prompt = Some(LoginPrompt::new(None));
+                ctx.new_data_request = Some((req, view_id));
+            }
+            Err(e) => {
+                debug!("{req} failed: {e}");
+                // a rejected commit keeps the dialog open, so the message can be fixed
+                if view_id == ViewId::Commit {
+                    if let Some(dialog) = &mut self.commit_dialog {
+                        dialog.state = CommitState::Failed(e.to_string());
+                    }
+                }
+                if view_id == ViewId::Finder {
+                    if let Some(finder) = &mut self.finder {
+                        finder.stop_listing();
+                    }
+                }
+                // failed fetches are only reported; navigation state is left as it was
+                ctx.error_log.push(FailedRequest {
+                    req,
+                    view_id,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    /// follow-ups of whatever just happened: views opened by a pane, paged and debounced
+    /// requests, a finished commit, and sending the pending request
+    pub(crate) fn update(&mut self) {
+        let ctx = &mut self.ctx;
+        if let Some(browser) = ctx.open_log_browser.take() {
+            self.log_browser = Some(browser);
+        }
+        if ctx.new_data_request.is_none() {
+            if let Some(req) = self
+                .log_browser
+                .as_mut()
+                .and_then(LogBrowser::next_page_request)
+            {
+                ctx.new_data_request = Some((req, ViewId::LogBrowser));
+            }
+        }
+
+        self.panes.update(ctx);
+
+        if let Some(CommitState::Done(summary)) = self.commit_dialog.as_ref().map(|d| &d.state) {
+            ctx.message = summary.clone();
+            self.commit_dialog = None;
+            if let Some(status) = &self.status_view {
+                ctx.new_data_request = Some((
+                    DataRequest::Status(TargetUrl(status.wc().to_owned()), status.incoming()),
+                    ViewId::Status,
+                ));
+            }
+        }
+
+        if self.login_prompt.is_some() {
+            return;
+        }
+        if let Some((req, view_id)) = ctx.new_data_request.take() {
+            let tx = self.events.clone();
+            let sent = req.clone();
+            Arc::clone(&ctx.data_handler).request(req, view_id, move |res_resp| {
+                // the ui only goes away on quit, then nobody waits for the response anyway
+                let _ = tx.send(AppEvent::Response(
+                    sent.clone(),
+                    view_id,
+                    Box::new(res_resp),
+                ));
+            });
+        }
+    }
+
+    pub(crate) fn render(&mut self, frame: &mut UiFrame) {
+        let areas = self.panes.layout(frame.size());
+        self.panes.render(frame, &areas, &mut self.ctx);
+
+        // full views cover the lists; the text pane stays visible next to them, to show diffs.
+        // with no list on screen they take all of it
+        let views = areas
+            .iter()
+            .filter(|(id, _)| matches!(id, PaneId::Pprev | PaneId::Prev | PaneId::List))
+            .map(|(_, area)| *area)
+            .reduce(|a, b| a.union(b))
+            .unwrap_or_else(|| frame.size());
+
+        if let Some(status) = &self.status_view {
+            frame.render_widget(Clear, views);
+            status.render(frame, views);
+        }
+
+        if let Some(dialog) = &self.commit_dialog {
+            frame.render_widget(Clear, views);
+            dialog.render(frame, views);
+        }
+
+        if let Some(browser) = &mut self.log_browser {
+            frame.render_widget(Clear, views);
+            browser.render(frame, views);
+        }
+
+        if let (true, Some(finder)) = (self.finder_open, &mut self.finder) {
+            finder.render(frame);
+        }
+
+        if self.ctx.error_log.is_open() {
+            self.ctx.error_log.render(frame);
+        }
+
+        if let Some(popup) = &self.cache_popup {
+            popup.render(frame);
+        }
+
+        if self.activity_panel.is_open() {
+            self.activity_panel
+                .render(frame, &self.ctx.data_handler.activity());
+        }
+
+        if let Some(prompt) = &self.login_prompt {
+            prompt.render(frame);
+        }
+
+        if self.quitting {
+            let area = ui::centered_rect(30, 10, frame.size());
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(QUIT_PROMPT).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme::fg(Slot::BorderAlert))
+                        .border_type(BorderType::Thick),
+                ),
+                area,
+            );
+        }
+    }
+}
+
+fn status_help(wc: &str) -> String {
+    format!(
+        "status of '{wc}' : [diff: {}], [mark: {}], [commit: {}], [incoming: {}], [refresh: {}], \
+         [back: {}]",
+        keymap::key(Action::Diff),
+        keymap::key(Action::Mark),
+        keymap::key(Action::Commit),
+        keymap::key(Action::ToggleIncoming),
+        keymap::key(Action::Refresh),
+        keymap::key(Action::Back)
+    )
+}
@@ -0,0 +1,278 @@
+use crate::{
+    keymap::{self, Action},
+    theme::{self, Slot},
+};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+/// width of the date column of `svn blame -v`, blank but for a `-` on uncommitted lines
+const DATE_WIDTH: usize = 44;
+/// background per age bucket, newest first; older lines keep the terminal background
+const AGE_SLOTS: [(i64, Slot); 5] = [
+    (7, Slot::BlameAge1),
+    (30, Slot::BlameAge2),
+    (180, Slot::BlameAge3),
+    (365, Slot::BlameAge4),
+    (3 * 365, Slot::BlameAge5),
+];
+
+/// one line of `svn blame -v`; locally modified lines have no revision
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct BlameLine {
+    pub(crate) revision: Option<u32>,
+    author: String,
+    /// days since the unix epoch of the last change
+    day: Option<i64>,
+    text: String,
+}
+
+/// parses the plain text output of `svn blame -v`
+///
+/// "  1234    author 2023-01-31 10:00:00 +0000 (Tue, 31 Jan 2023) line content"
+pub(crate) fn parse(text: &str) -> Vec<BlameLine> {
+    text.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<BlameLine> {
+    let rest = line.trim_start();
+    let (rev, rest) = rest.split_once(' ')?;
+    let rest = rest.trim_start();
+    let (author, rest) = rest.split_once(' ')?;
+    if rev == "-" {
+        // uncommitted line: no date, the content starts after the blank date column
+        return Some(BlameLine {
+            revision: None,
+            author: author.to_owned(),
+            day: None,
+            text: rest.get(DATE_WIDTH + 1..).unwrap_or_default().to_owned(),
+        });
+    }
+    let rest = rest.trim_start();
+    let day = parse_day(rest.get(..10)?);
+    let (_, content) = rest.split_once(") ").unwrap_or((rest, ""));
+    Some(BlameLine {
+        revision: rev.parse().ok(),
+        author: author.to_owned(),
+        day,
+        text: content.to_owned(),
+    })
+}
+
+/// "2023-01-31" as days since the unix epoch
+fn parse_day(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|p| p.parse::<i64>());
+    let (y, m, d) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    // days_from_civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146097 + doe - 719468)
+}
+
+fn today() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64 / 86400)
+}
+
+fn age_str(days: i64) -> String {
+    match days {
+        d if d < 1 => "today".to_owned(),
+        d if d < 30 => format!("{d}d"),
+        d if d < 365 => format!("{}mo", d / 30),
+        d => format!("{}y", d / 365),
+    }
+}
+
+pub(crate) enum BlameOutcome {
+    Browsing,
+    Close,
+    /// open log and diff of this revision
+    ShowRevision(u32),
+    /// blame again, as of this revision
+    Reblame(u32),
+}
+
+/// `svn blame` of one file, with a line cursor
+pub(crate) struct BlameView {
+    url: String,
+    revision: Option<u32>,
+    lines: Vec<BlameLine>,
+    cursor: usize,
+    scroll: usize,
+    page: usize,
+}
+
+impl BlameView {
+    pub(crate) fn new(url: String, revision: Option<u32>, lines: Vec<BlameLine>) -> Self {
+        Self {
+            url,
+            revision,
+            lines,
+            cursor: 0,
+            scroll: 0,
+            page: 1,
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub(crate) fn title(&self) -> String {
+        let at = self.revision.map(|r| format!("@{r}")).unwrap_or_default();
+        format!(
+            "blame {}{at} : [log+diff: {}], [blame before line's revision: {}], [close: {}]",
+            self.url,
+            keymap::key(Action::ShowRevision),
+            keymap::key(Action::Reblame),
+            keymap::key(Action::Back)
+        )
+    }
+
+    /// None for actions blame has no use for, so they still do what they do elsewhere
+    pub(crate) fn handle(&mut self, action: Action) -> Option<BlameOutcome> {
+        let last = self.lines.len().saturating_sub(1);
+        match action {
+            Action::Cancel | Action::Back => return Some(BlameOutcome::Close),
+            Action::Down => self.cursor = (self.cursor + 1).min(last),
+            Action::Up => self.cursor = self.cursor.saturating_sub(1),
+            Action::PageDown | Action::ScrollTextDown => {
+                self.cursor = (self.cursor + self.page).min(last)
+            }
+            Action::PageUp | Action::ScrollTextUp => {
+                self.cursor = self.cursor.saturating_sub(self.page)
+            }
+            Action::Top => self.cursor = 0,
+            Action::Bottom => self.cursor = last,
+            Action::ShowRevision | Action::Open => {
+                if let Some(rev) = self.current_revision() {
+                    return Some(BlameOutcome::ShowRevision(rev));
+                }
+            }
+            Action::Reblame => {
+                if let Some(rev) = self.current_revision().filter(|&r| r > 1) {
+                    return Some(BlameOutcome::Reblame(rev - 1));
+                }
+            }
+            _ => return None,
+        }
+        Some(BlameOutcome::Browsing)
+    }
+
+    fn current_revision(&self) -> Option<u32> {
+        self.lines.get(self.cursor).and_then(|l| l.revision)
+    }
+
+    pub(crate) fn rows(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub(crate) fn render<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect, block: Block) {
+        let height = usize::from(block.inner(area).height.max(1));
+        self.page = height;
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + height {
+            self.scroll = self.cursor + 1 - height;
+        }
+        let today = today();
+        // only the lines on screen are built
+        let text: Vec<Spans> = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+            .map(|(n, line)| {
+                let age = line.day.map(|d| today - d);
+                let bg = age
+                    .and_then(|a| AGE_SLOTS.iter().find(|(max, _)| a <= *max))
+                    .map_or(Color::Reset, |(_, slot)| theme::color(*slot));
+                let mut gutter_style = theme::fg(Slot::Accent).bg(bg);
+                if n == self.cursor {
+                    gutter_style = gutter_style.add_modifier(Modifier::REVERSED);
+                }
+                let rev = line
+                    .revision
+                    .map_or_else(|| "-".to_owned(), |r| format!("r{r}"));
+                Spans::from(vec![
+                    Span::styled(
+                        format!(
+                            "{rev:>7} {:<10.10} {:>5} ",
+                            line.author,
+                            age.map(age_str).unwrap_or_default()
+                        ),
+                        gutter_style,
+                    ),
+                    Span::styled(line.text.clone(), Style::default().bg(bg)),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(text).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_committed_line() {
+        let line = parse_line(
+            "  1234      alice 2023-01-31 10:00:00 +0000 (Tue, 31 Jan 2023)     let x = (1) + 2;",
+        )
+        .unwrap();
+        assert_eq!(line.revision, Some(1234));
+        assert_eq!(line.author, "alice");
+        assert_eq!(line.day, Some(19388));
+        assert_eq!(line.text, "    let x = (1) + 2;");
+    }
+
+    #[test]
+    fn parse_uncommitted_line() {
+        let line = parse_line(&format!(
+            "{:>6} {:>10} {:>44}     changed here",
+            "-", "-", "-"
+        ))
+        .unwrap();
+        assert_eq!(line.revision, None);
+        assert_eq!(line.day, None);
+        assert_eq!(line.text, "    changed here");
+    }
+
+    #[test]
+    fn parse_skips_short_lines() {
+        assert_eq!(parse("\n  12\n").len(), 0);
+    }
+
+    #[test]
+    fn days_since_epoch() {
+        assert_eq!(parse_day("1970-01-01"), Some(0));
+        assert_eq!(parse_day("2000-03-01"), Some(11017));
+        assert_eq!(parse_day("2024-02-29"), Some(19782));
+        assert_eq!(parse_day("20x3-01-01"), None);
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(age_str(0), "today");
+        assert_eq!(age_str(12), "12d");
+        assert_eq!(age_str(95), "3mo");
+        assert_eq!(age_str(800), "2y");
+    }
+}
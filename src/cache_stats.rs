@@ -0,0 +1,141 @@
+use crate::{
+    keymap::{self, Action},
+    theme::{self, Slot},
+    ui::centered_rect,
+};
+use std::time::SystemTime;
+use tui::{
+    backend::Backend,
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// snapshot of the response cache, taken when the popup is opened or the cache is cleared
+#[derive(Default)]
+pub(crate) struct CacheStats {
+    pub(crate) entries: usize,
+    /// json size of the cached responses, a rough idea of their memory use
+    pub(crate) memory_bytes: usize,
+    pub(crate) hits: usize,
+    pub(crate) misses: usize,
+    /// the entry checked against the repository the longest time ago
+    pub(crate) oldest: Option<(String, SystemTime)>,
+    /// files and bytes of the on-disk cache, if it's enabled
+    pub(crate) disk: Option<(usize, u64)>,
+}
+
+pub(crate) enum CacheOutcome {
+    Browsing,
+    Close,
+    ClearSubtree,
+    ClearAll,
+}
+
+/// popup with the cache statistics, and the commands to clear it
+pub(crate) struct CachePopup {
+    stats: CacheStats,
+    /// url the subtree command clears
+    subtree: String,
+}
+
+impl CachePopup {
+    pub(crate) fn new(stats: CacheStats, subtree: String) -> Self {
+        Self { stats, subtree }
+    }
+
+    pub(crate) fn subtree(&self) -> &str {
+        &self.subtree
+    }
+
+    pub(crate) fn set_stats(&mut self, stats: CacheStats) {
+        self.stats = stats;
+    }
+
+    pub(crate) fn handle(&mut self, action: Action) -> CacheOutcome {
+        match action {
+            Action::Cancel | Action::Back | Action::Cache => CacheOutcome::Close,
+            Action::ClearSubtree => CacheOutcome::ClearSubtree,
+            Action::ClearAll => CacheOutcome::ClearAll,
+            _ => CacheOutcome::Browsing,
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(&self, frame: &mut Frame<B>) {
+        let area = centered_rect(60, 40, frame.size());
+        let stats = &self.stats;
+        let lookups = stats.hits + stats.misses;
+        let hit_rate = if lookups == 0 {
+            "-".to_owned()
+        } else {
+            format!(
+                "{:.0}% ({} of {lookups})",
+                stats.hits as f64 * 100.0 / lookups as f64,
+                stats.hits
+            )
+        };
+        let oldest = match &stats.oldest {
+            Some((req, checked)) => {
+                let secs = checked.elapsed().map_or(0, |d| d.as_secs());
+                format!("{req}, checked {} ago", duration_str(secs))
+            }
+            None => "-".to_owned(),
+        };
+        let disk = match stats.disk {
+            Some((files, bytes)) => format!("{files} entries, {}", size_str(bytes as usize)),
+            None => "disabled".to_owned(),
+        };
+        let row = |label: &str, value: String| {
+            Spans::from(vec![
+                Span::styled(format!("{label:>10}: "), theme::fg(Slot::Muted)),
+                Span::raw(value),
+            ])
+        };
+        let text = vec![
+            row("entries", stats.entries.to_string()),
+            row("memory", format!("~{}", size_str(stats.memory_bytes))),
+            row("hit rate", hit_rate),
+            row("oldest", oldest),
+            row("on disk", disk),
+            Spans::default(),
+            row("subtree", self.subtree.clone()),
+        ];
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(text).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title())
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            ),
+            area,
+        );
+    }
+}
+
+fn size_str(bytes: usize) -> String {
+    match bytes {
+        b if b < 1024 => format!("{b} B"),
+        b if b < 1024 * 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+fn duration_str(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+fn title() -> String {
+    format!(
+        "cache : [clear subtree: {}], [clear all: {}], [close: {}]",
+        keymap::key(Action::ClearSubtree),
+        keymap::key(Action::ClearAll),
+        keymap::keys(&[Action::Cancel, Action::Back])
+    )
+}
@@ -0,0 +1,94 @@
+use crate::{
+    config::Login,
+    data_handler::QUICK_TIMEOUT,
+    lister::svn_helper::{self, CancelToken},
+    CustomError,
+};
+use clap::Parser;
+use log::debug;
+use std::path::{Path, PathBuf};
+
+const URL_SCHEMES: [&str; 5] = ["http://", "https://", "svn://", "svn+ssh://", "file://"];
+
+/// terminal ui to browse subversion repositories
+#[derive(Parser)]
+#[command(version, about)]
+pub(crate) struct Cli {
+    /// repository url or working-copy path to start from [default: .]
+    pub(crate) target: Option<String>,
+    /// browse the repository as of this revision (number or HEAD)
+    #[arg(short, long, value_parser = parse_revision)]
+    pub(crate) revision: Option<String>,
+    /// config file to use instead of the default one
+    #[arg(short, long)]
+    pub(crate) config: Option<PathBuf>,
+    /// svn username, takes precedence over the environment and the config file
+    #[arg(short, long)]
+    pub(crate) username: Option<String>,
+    /// always fetch from the server, never from the cache
+    #[arg(long)]
+    pub(crate) no_cache: bool,
+    /// write log output to this file instead of stderr
+    #[arg(long)]
+    pub(crate) log_file: Option<PathBuf>,
+    /// print the default keymap, to be copied into the config file, and exit
+    #[arg(long)]
+    pub(crate) dump_default_keymap: bool,
+}
+
+fn parse_revision(rev: &str) -> Result<String, String> {
+    if rev.eq_ignore_ascii_case("HEAD") {
+        Ok("HEAD".to_owned())
+    } else if !rev.is_empty() && rev.chars().all(|c| c.is_ascii_digit()) {
+        Ok(rev.to_owned())
+    } else {
+        Err(format!("'{rev}' is neither a revision number nor HEAD"))
+    }
+}
+
+impl Cli {
+    /// working-copy path the tui was started from, if it wasn't started from a url
+    pub(crate) fn working_copy(&self) -> Option<String> {
+        let target = self.target.as_deref().unwrap_or(".");
+        (!URL_SCHEMES.iter().any(|s| target.starts_with(s))).then(|| target.to_owned())
+    }
+
+    /// url the tui starts browsing from, always ending with '/'
+    ///
+    /// local paths are resolved through the working copy; urls are checked with `svn info`.
+    /// authentication failures are let through, so the login prompt can deal with them.
+    pub(crate) fn resolve_base_url(&self, login: Option<&Login>) -> Result<String, CustomError> {
+        let target = self.target.as_deref().unwrap_or(".");
+        let is_url = URL_SCHEMES.iter().any(|s| target.starts_with(s));
+        if !is_url && !Path::new(target).exists() {
+            return Err(CustomError::InvalidTarget(format!(
+                "'{target}' is neither a repository url nor an existing path"
+            )));
+        }
+        let info = svn_helper::info(login, target, &CancelToken::default(), Some(QUICK_TIMEOUT));
+        let mut url = match info {
+            Ok(info) => info.url,
+            Err(e) if is_url && e.is_auth() => {
+                debug!("authentication needed for {target}");
+                target.to_owned()
+            }
+            Err(CustomError::TimedOut(t)) => {
+                return Err(CustomError::InvalidTarget(format!(
+                    "'{target}' did not answer within {}s",
+                    t.as_secs()
+                )))
+            }
+            Err(e) => {
+                return Err(CustomError::InvalidTarget(if is_url {
+                    format!("'{target}' is not a valid repository url: {e}")
+                } else {
+                    format!("'{target}' is not a working copy: {e}")
+                }))
+            }
+        };
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        Ok(url)
+    }
+}
@@ -0,0 +1,179 @@
+use crate::theme::{self, Slot};
+use crossterm::event::{KeyCode, KeyModifiers};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+const EDITOR: &str = "commit message : [commit: Ctrl-s], [cancel: Esc], [scroll diff: PgUp/PgDn]";
+
+pub(crate) enum CommitState {
+    Editing,
+    Committing,
+    /// commit went through, with the summary line printed by svn
+    Done(String),
+    Failed(String),
+}
+
+pub(crate) enum CommitOutcome {
+    Editing,
+    Cancel,
+    /// stop the running commit, the dialog stays open
+    Abort,
+    Submit(Vec<String>, String),
+    ScrollPreview(bool),
+}
+
+/// message editor for committing the paths marked in the status view
+pub(crate) struct CommitDialog {
+    paths: Vec<String>,
+    message: String,
+    /// cursor position in chars
+    cursor: usize,
+    pub(crate) state: CommitState,
+}
+
+impl CommitDialog {
+    pub(crate) fn new(paths: Vec<String>) -> Self {
+        Self {
+            paths,
+            message: String::new(),
+            cursor: 0,
+            state: CommitState::Editing,
+        }
+    }
+
+    fn byte_pos(&self) -> usize {
+        self.message
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.message.len(), |(i, _)| i)
+    }
+
+    pub(crate) fn handle(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommitOutcome {
+        if matches!(self.state, CommitState::Committing) {
+            if code != KeyCode::Esc {
+                return CommitOutcome::Editing;
+            }
+            self.state = CommitState::Failed("cancelled".to_owned());
+            return CommitOutcome::Abort;
+        }
+        let chars = self.message.chars().count();
+        match code {
+            KeyCode::Esc => return CommitOutcome::Cancel,
+            KeyCode::Char('s') if modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.message.trim().is_empty() {
+                    self.state = CommitState::Failed("the commit message is empty".to_owned());
+                } else {
+                    self.state = CommitState::Committing;
+                    return CommitOutcome::Submit(self.paths.clone(), self.message.clone());
+                }
+            }
+            KeyCode::PageUp => return CommitOutcome::ScrollPreview(true),
+            KeyCode::PageDown => return CommitOutcome::ScrollPreview(false),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(chars),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = chars,
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let pos = self.byte_pos();
+                self.message.remove(pos);
+            }
+            KeyCode::Delete if self.cursor < chars => {
+                let pos = self.byte_pos();
+                self.message.remove(pos);
+            }
+            KeyCode::Enter => {
+                let pos = self.byte_pos();
+                self.message.insert(pos, '\n');
+                self.cursor += 1;
+            }
+            KeyCode::Char(c) => {
+                let pos = self.byte_pos();
+                self.message.insert(pos, c);
+                self.cursor += 1;
+            }
+            _ => {}
+        }
+        CommitOutcome::Editing
+    }
+
+    pub(crate) fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+        let items: Vec<ListItem> = self
+            .paths
+            .iter()
+            .map(|p| ListItem::new(p.as_str()))
+            .collect();
+        frame.render_widget(
+            List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("committing {} path(s)", self.paths.len())),
+            ),
+            chunks[0],
+        );
+
+        // the cursor is drawn as a reversed cell, so it works without a terminal cursor
+        let pos = self.byte_pos();
+        let (before, after) = self.message.split_at(pos);
+        let mut after_chars = after.chars();
+        let under_cursor = match after_chars.next() {
+            Some('\n') | None => " ".to_owned(),
+            Some(c) => c.to_string(),
+        };
+        let rest = if after.starts_with('\n') {
+            after
+        } else {
+            after_chars.as_str()
+        };
+        let mut text: Vec<Spans> = vec![];
+        let mut lines = before.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            if lines.peek().is_some() {
+                text.push(Spans::from(line.to_owned()));
+            } else {
+                let mut rest_lines = rest.split('\n');
+                let mut spans = vec![
+                    Span::raw(line.to_owned()),
+                    Span::styled(
+                        under_cursor.clone(),
+                        Style::default().add_modifier(Modifier::REVERSED),
+                    ),
+                ];
+                spans.extend(rest_lines.next().map(|l| Span::raw(l.to_owned())));
+                text.push(Spans::from(spans));
+                text.extend(rest_lines.map(|l| Spans::from(l.to_owned())));
+            }
+        }
+        let status = match &self.state {
+            CommitState::Editing | CommitState::Done(_) => None,
+            CommitState::Committing => {
+                Some(("committing... [stop: Esc]".to_owned(), Slot::Warning))
+            }
+            CommitState::Failed(e) => Some((e.clone(), Slot::Error)),
+        };
+        if let Some((status, slot)) = status {
+            text.push(Spans::default());
+            text.push(Spans::from(Span::styled(status, theme::fg(slot))));
+        }
+        frame.render_widget(
+            Paragraph::new(text).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(EDITOR)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            ),
+            chunks[1],
+        );
+    }
+}
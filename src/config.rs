@@ -0,0 +1,157 @@
+use crate::{keymap::Action, theme::ThemeName, ui::PaneId, CustomError};
+use log::debug;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const APP_DIR: &str = "svn-tui";
+const CONFIG_FILE: &str = "config.toml";
+const ENV_USERNAME: &str = "SVN_TUI_USERNAME";
+const ENV_PASSWORD: &str = "SVN_TUI_PASSWORD";
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Config {
+    pub(crate) credentials: CredentialsConfig,
+    pub(crate) cache: CacheConfig,
+    pub(crate) prefetch: PrefetchConfig,
+    pub(crate) theme: ThemeConfig,
+    pub(crate) layout: LayoutConfig,
+    /// keys of the listed actions, replacing their defaults
+    pub(crate) keymap: HashMap<Action, Vec<String>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct CredentialsConfig {
+    pub(crate) username: Option<String>,
+    pub(crate) password: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct CacheConfig {
+    /// keep responses under the xdg cache dir, so they survive a restart
+    pub(crate) persistent: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct PrefetchConfig {
+    /// directory levels listed below the selected one; 0 turns prefetching off
+    pub(crate) depth: usize,
+    /// directories above and below the selected one that are listed as well
+    pub(crate) siblings: usize,
+    /// prefetching svn processes running at the same time
+    pub(crate) concurrency: usize,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            siblings: 0,
+            concurrency: 2,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct ThemeConfig {
+    /// built-in theme: dark, light or high-contrast
+    pub(crate) name: ThemeName,
+    /// theme file whose colours replace those of the built-in theme; `theme.toml` next to the
+    /// config file is used if it exists
+    pub(crate) file: Option<PathBuf>,
+    /// 16 for terminals that can't show more; guessed from $TERM and $COLORTERM when unset
+    pub(crate) colors: Option<u16>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct LayoutConfig {
+    /// heights of the messages row, the lists and the bottom row, relative to each other
+    pub(crate) rows: [u16; 3],
+    /// widths of the grandparent list, the parent list, the list and the text pane
+    pub(crate) columns: [u16; 4],
+    /// widths of the info and commit message panes
+    pub(crate) bottom: [u16; 2],
+    /// panes left out; the others share their space
+    pub(crate) hidden: Vec<PaneId>,
+    /// on terminals narrower than this the panes are stacked, without the parent lists
+    pub(crate) single_column_width: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            rows: [7, 80, 13],
+            columns: [15, 15, 20, 50],
+            bottom: [30, 70],
+            hidden: vec![],
+            single_column_width: 100,
+        }
+    }
+}
+
+/// username/password pair handed to every svn invocation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Login {
+    pub(crate) username: String,
+    pub(crate) password: String,
+}
+
+/// outcome of credential resolution, before the interactive prompt is consulted
+pub(crate) enum ResolvedLogin {
+    /// nothing configured: let svn use its own auth cache
+    None,
+    /// username known, password must be asked for
+    UsernameOnly(String),
+    Complete(Login),
+}
+
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join(APP_DIR))
+}
+
+impl Config {
+    pub(crate) fn default_path() -> Option<PathBuf> {
+        config_dir().map(|d| d.join(CONFIG_FILE))
+    }
+
+    /// loads the given file, or the default one if it exists; a missing default file is not an error
+    pub(crate) fn load(path: Option<&Path>) -> Result<Self, CustomError> {
+        let path = match path {
+            Some(p) => p.to_owned(),
+            None => match Self::default_path() {
+                Some(p) if p.exists() => p,
+                _ => return Ok(Config::default()),
+            },
+        };
+        debug!("loading config from {path:?}");
+        let content = fs::read_to_string(&path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// the command line takes precedence over environment variables, which take precedence
+    /// over the config file
+    pub(crate) fn resolve_login(&self, cli_username: Option<String>) -> ResolvedLogin {
+        let username = cli_username
+            .or_else(|| env::var(ENV_USERNAME).ok())
+            .or_else(|| self.credentials.username.clone());
+        let password = env::var(ENV_PASSWORD)
+            .ok()
+            .or_else(|| self.credentials.password.clone());
+        match (username, password) {
+            (Some(username), Some(password)) => {
+                ResolvedLogin::Complete(Login { username, password })
+            }
+            (Some(username), None) => ResolvedLogin::UsernameOnly(username),
+            (None, _) => ResolvedLogin::None,
+        }
+    }
+}
@@ -1,17 +1,130 @@
-use crate::{lister::svn_helper, CustomError, MAX_VALIDITY_OF_CACHED_LIST};
+use crate::{
+    blame::{self, BlameLine},
+    cache_stats::CacheStats,
+    config::{Login, PrefetchConfig},
+    diff::{Diff, DiffRange},
+    disk_cache::DiskCache,
+    lister::{
+        svn_helper::{self, CancelToken},
+        ListEntry, PathType,
+    },
+    log_browser::LOG_PAGE_SIZE,
+    revlog::{self, LogEntry},
+    status::{self, StatusEntry},
+    CustomError,
+};
 use log::debug;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-    thread::{self, ThreadId},
-    time::SystemTime,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
-use svn_cmd::{SvnInfo, SvnList, SvnLog};
+
+/// entries checked against the repository this recently are trusted without asking again
+const RECHECK_AFTER: Duration = Duration::from_secs(30);
+/// svn processes running at the same time
+const WORKERS: usize = 4;
+/// for requests the server answers right away
+pub(crate) const QUICK_TIMEOUT: Duration = Duration::from_secs(30);
+/// for requests that may transfer whole files or long histories
+const SLOW_TIMEOUT: Duration = Duration::from_secs(120);
+/// for a commit, which sends every changed file
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// attempts of a request failing with a transient error, the first one included
+const MAX_ATTEMPTS: u32 = 3;
+/// wait before the first retry, doubled for every one after it
+const FIRST_BACKOFF: Duration = Duration::from_millis(500);
+/// a response that comes in bit by bit is handed over this often while it lasts
+const STREAM_INTERVAL: Duration = Duration::from_millis(100);
+
+/// a queued request
+struct Job {
+    id: u64,
+    req: DataRequest,
+    view_id: ViewId,
+    cancel: CancelToken,
+    queued: Instant,
+}
+
+/// a directory to list ahead of time, and how many levels below it
+struct PrefetchJob {
+    url: String,
+    depth: usize,
+    cancel: CancelToken,
+}
+
+/// requests come first; prefetches only run when no request is waiting
+#[derive(Default)]
+struct Queues {
+    jobs: VecDeque<Job>,
+    prefetch: VecDeque<PrefetchJob>,
+}
+
+enum Work {
+    Request(Job),
+    Prefetch(PrefetchJob),
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum ActivityState {
+    Queued,
+    Running,
+    /// a cached response was delivered, svn is asked whether it's still current
+    Revalidating,
+    /// waiting for or running this attempt, after transient errors
+    Retrying(u32),
+}
+
+/// a queued or running request, for the activity panel
+pub(crate) struct Activity {
+    pub(crate) req: DataRequest,
+    pub(crate) view_id: ViewId,
+    pub(crate) state: ActivityState,
+    /// when it was queued, or when a worker took it
+    pub(crate) since: Instant,
+}
+
+/// the latest request of a view, whose responses are handed to `cb`
+struct Pending {
+    id: u64,
+    cancel: CancelToken,
+    cb: Box<ResponseCb>,
+}
 
 #[derive(Default)]
 pub(crate) struct DataHandler {
-    thread_ids: Arc<Mutex<HashMap<ViewId, (ThreadId, Box<ResponseCb>)>>>,
-    cache: Arc<Mutex<HashMap<DataRequest, (DataResponse, SystemTime)>>>,
+    pending: Mutex<HashMap<ViewId, Pending>>,
+    queue: Mutex<Queues>,
+    queue_ready: Condvar,
+    prefetch: PrefetchConfig,
+    prefetching: AtomicUsize,
+    /// shared by the prefetches of the current selection
+    prefetch_cancel: Mutex<CancelToken>,
+    /// requests taken by a worker, by id
+    running: Mutex<HashMap<u64, (DataRequest, ViewId, Instant)>>,
+    workers_started: AtomicBool,
+    next_id: AtomicU64,
+    cache: Arc<Mutex<HashMap<DataRequest, CacheEntry>>>,
+    /// cached responses already shown, while they are checked against the repository
+    revalidating: Mutex<HashSet<DataRequest>>,
+    /// requests waiting to be retried, with the number of the next attempt
+    retrying: Mutex<HashMap<DataRequest, u32>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    login: Mutex<Option<Login>>,
+    revision: Option<String>,
+    no_cache: bool,
+    /// responses are also kept on disk, once the repository uuid is known
+    persistent: bool,
+    disk_cache: Mutex<Option<Arc<DiskCache>>>,
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
@@ -19,10 +132,117 @@ pub(crate) struct TargetUrl(pub(crate) String);
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 pub(crate) enum DataRequest {
-    Info(TargetUrl),
     List(TargetUrl),
+    /// `svn list -R`, for the finder
+    Tree(TargetUrl),
     Log(TargetUrl),
+    /// one page of `svn log -v`, starting at the given revision or at HEAD
+    LogPage(TargetUrl, Option<u32>),
     Text(TargetUrl),
+    Diff(TargetUrl, DiffRange),
+    /// `svn blame -v`, as of the given revision or the peg revision
+    Blame(TargetUrl, Option<u32>),
+    /// `svn status` of a working-copy path, with `-u` if true
+    Status(TargetUrl, bool),
+    /// combined diff against BASE of several working-copy paths
+    BaseDiff(Vec<String>),
+    /// `svn commit` of exactly these working-copy paths, with the given message; the deleted
+    /// directories among them, which svn only commits with everything below them, go first
+    Commit(Vec<String>, String, Vec<String>),
+}
+
+impl DataRequest {
+    /// working-copy state changes behind our back, so it's always fetched fresh
+    fn cacheable(&self) -> bool {
+        !matches!(
+            self,
+            DataRequest::Status(..)
+                | DataRequest::BaseDiff(_)
+                | DataRequest::Commit(..)
+                | DataRequest::Diff(_, DiffRange::Base | DiffRange::Incoming)
+        )
+    }
+
+    /// svn is killed when it takes longer
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            DataRequest::List(_) | DataRequest::Log(_) | DataRequest::LogPage(..) => {
+                Some(QUICK_TIMEOUT)
+            }
+            DataRequest::Text(_)
+            | DataRequest::Diff(..)
+            | DataRequest::Blame(..)
+            | DataRequest::Status(..)
+            | DataRequest::BaseDiff(_) => Some(SLOW_TIMEOUT),
+            // Esc stops it sooner
+            DataRequest::Commit(..) => Some(COMMIT_TIMEOUT),
+            // a large tree takes as long as it takes, it is shown while it comes in
+            DataRequest::Tree(_) => None,
+        }
+    }
+
+    /// a commit may have gone through even when svn reports an error, it's never repeated
+    pub(crate) fn retryable(&self) -> bool {
+        !matches!(self, DataRequest::Commit(..))
+    }
+
+    /// the response can't change anymore: it's tied to a revision number, not to HEAD
+    fn immutable(&self, peg: Option<&str>) -> bool {
+        let pegged = peg.is_some_and(|p| p.parse::<u32>().is_ok());
+        match self {
+            DataRequest::Diff(_, range) => range.peg().is_some(),
+            // pegged at its revision; a log page only starts at one, the path is still HEAD's
+            DataRequest::Blame(_, Some(_)) => true,
+            DataRequest::List(_)
+            | DataRequest::Tree(_)
+            | DataRequest::Log(_)
+            | DataRequest::LogPage(..)
+            | DataRequest::Text(_)
+            | DataRequest::Blame(..) => pegged,
+            _ => false,
+        }
+    }
+
+    /// key of the on-disk cache entry, for the requests worth keeping across sessions: what is
+    /// asked for, its revisions and the url, last so that it needs no escaping
+    fn disk_key(&self, peg: Option<&str>) -> Option<String> {
+        let rev = |rev: &Option<u32>| rev.map_or_else(|| "-".to_owned(), |r| r.to_string());
+        let (kind, args, TargetUrl(url)) = match self {
+            DataRequest::List(url) => ("list", "-".to_owned(), url),
+            DataRequest::Tree(url) => ("tree", "-".to_owned(), url),
+            DataRequest::Log(url) => ("log", "-".to_owned(), url),
+            DataRequest::LogPage(url, from) => ("log-page", rev(from), url),
+            DataRequest::Text(url) => ("cat", "-".to_owned(), url),
+            DataRequest::Diff(url, DiffRange::Change(rev)) => ("diff", format!("c{rev}"), url),
+            DataRequest::Diff(url, DiffRange::Range(from, to)) => {
+                ("diff", format!("r{from}:{to}"), url)
+            }
+            DataRequest::Blame(url, at) => ("blame", rev(at), url),
+            DataRequest::Diff(_, DiffRange::Base | DiffRange::Incoming)
+            | DataRequest::Status(..)
+            | DataRequest::BaseDiff(_)
+            | DataRequest::Commit(..) => return None,
+        };
+        Some(format!("{kind} {args} {} {url}", peg.unwrap_or("HEAD")))
+    }
+}
+
+impl fmt::Display for DataRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataRequest::List(TargetUrl(u)) => write!(f, "svn list {u}"),
+            DataRequest::Tree(TargetUrl(u)) => write!(f, "svn list -R {u}"),
+            DataRequest::Log(TargetUrl(u)) | DataRequest::LogPage(TargetUrl(u), _) => {
+                write!(f, "svn log {u}")
+            }
+            DataRequest::Text(TargetUrl(u)) => write!(f, "svn cat {u}"),
+            DataRequest::Diff(TargetUrl(u), range) => write!(f, "svn {range} {u}"),
+            DataRequest::Blame(TargetUrl(u), _) => write!(f, "svn blame {u}"),
+            DataRequest::Status(TargetUrl(u), _) => write!(f, "svn status {u}"),
+            DataRequest::BaseDiff(paths) => write!(f, "svn diff of {} path(s)", paths.len()),
+            DataRequest::Commit(paths, ..) => write!(f, "svn commit of {} path(s)", paths.len()),
+        }
+    }
 }
 
 impl From<TargetUrl> for String {
@@ -35,120 +255,772 @@ impl From<DataRequest> for TargetUrl {
     fn from(r: DataRequest) -> Self {
         match r {
             DataRequest::Log(u) => u,
+            DataRequest::LogPage(u, _) => u,
             DataRequest::List(u) => u,
-            DataRequest::Info(u) => u,
+            DataRequest::Tree(u) => u,
             DataRequest::Text(u) => u,
+            DataRequest::Diff(u, _) => u,
+            DataRequest::Blame(u, _) => u,
+            DataRequest::Status(u, _) => u,
+            DataRequest::BaseDiff(paths) | DataRequest::Commit(paths, ..) => {
+                TargetUrl(paths.join(" "))
+            }
         }
     }
 }
 
-#[derive(Clone)]
+/// status is never stored on disk, so it doesn't need to be serializable
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) enum DataResponse {
-    Info(SvnInfo),
-    List(SvnList),
-    Log(SvnLog),
+    List(Vec<ListEntry>),
+    /// every entry below the requested url, named by its path from there
+    Tree(Vec<ListEntry>),
+    /// entries of a tree still being listed, to put at the given index of what came before;
+    /// they are followed by the whole tree
+    #[serde(skip)]
+    TreePart(usize, Vec<ListEntry>),
+    Log(Vec<LogEntry>),
     Text(String),
+    Diff(Diff),
+    Blame(Vec<BlameLine>),
+    #[serde(skip)]
+    Status(Vec<StatusEntry>),
+    /// the fresh copy of a stale response delivered before; the view may have moved on since
+    #[serde(skip)]
+    Refreshed(Box<DataResponse>),
 }
 
-impl From<SvnInfo> for DataResponse {
-    fn from(i: SvnInfo) -> Self {
-        DataResponse::Info(i)
+impl DataResponse {
+    /// the response itself, whether or not it refreshes one delivered before
+    pub(crate) fn into_inner(self) -> Self {
+        match self {
+            DataResponse::Refreshed(response) => *response,
+            response => response,
+        }
     }
 }
-impl From<SvnList> for DataResponse {
-    fn from(l: SvnList) -> Self {
+
+impl From<Vec<ListEntry>> for DataResponse {
+    fn from(l: Vec<ListEntry>) -> Self {
         DataResponse::List(l)
     }
 }
-impl From<SvnLog> for DataResponse {
-    fn from(l: SvnLog) -> Self {
+impl From<Vec<LogEntry>> for DataResponse {
+    fn from(l: Vec<LogEntry>) -> Self {
         DataResponse::Log(l)
     }
 }
+impl From<Diff> for DataResponse {
+    fn from(d: Diff) -> Self {
+        DataResponse::Diff(d)
+    }
+}
+impl From<Vec<BlameLine>> for DataResponse {
+    fn from(b: Vec<BlameLine>) -> Self {
+        DataResponse::Blame(b)
+    }
+}
+impl From<Vec<StatusEntry>> for DataResponse {
+    fn from(s: Vec<StatusEntry>) -> Self {
+        DataResponse::Status(s)
+    }
+}
 impl From<String> for DataResponse {
     fn from(t: String) -> Self {
         DataResponse::Text(t)
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Clone, Copy)]
+/// a cached response, with the last-changed revision of its url when it was fetched
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CacheEntry {
+    response: DataResponse,
+    /// when the entry was fetched or last found to be up to date
+    checked: SystemTime,
+    /// none for responses tied to a fixed revision, or when svn couldn't tell
+    revision: Option<u32>,
+}
+
+impl CacheEntry {
+    fn new(response: DataResponse, revision: Option<u32>) -> Self {
+        Self {
+            response,
+            checked: SystemTime::now(),
+            revision,
+        }
+    }
+
+    fn recently_checked(&self) -> bool {
+        self.checked.elapsed().is_ok_and(|e| e < RECHECK_AFTER)
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 pub(crate) enum ViewId {
     MainList,
-    BottomInfo,
     BottomLog,
     RightInfoPane,
+    LogBrowser,
+    Status,
+    Commit,
+    Finder,
+    /// a speculative listing nobody waits for yet
+    Prefetch,
 }
 
 pub(crate) type ResultDataResponse = Result<DataResponse, CustomError>;
 type ResponseCb = dyn FnMut(ResultDataResponse) + Send;
 
 impl DataHandler {
-    pub(crate) fn request<F>(self: Arc<Self>, req: DataRequest, view_id: ViewId, f: F)
-    where
-        F: FnMut(ResultDataResponse) + Send + 'static,
-    {
-        let thread_ids = Arc::clone(&self.thread_ids);
-        let id = Arc::clone(&self).create_fetcher(req, move |svnlist_result, thread_id| {
-            let mut locked = thread_ids.lock().unwrap();
-            let (cur_id, cb) = locked.get_mut(&view_id).unwrap();
-            if cur_id == &thread_id {
-                (cb)(svnlist_result);
+    pub(crate) fn new(
+        revision: Option<String>,
+        no_cache: bool,
+        persistent: bool,
+        prefetch: PrefetchConfig,
+    ) -> Self {
+        Self {
+            revision,
+            no_cache,
+            persistent,
+            prefetch,
+            ..Default::default()
+        }
+    }
+
+    /// enables the on-disk cache of the repository `url` belongs to, if configured, once svn
+    /// told its uuid; until then responses are only cached in memory
+    ///
+    /// the repository uuid needs a working login, so this is tried again after logging in.
+    pub(crate) fn open_disk_cache(self: &Arc<Self>, url: &str) {
+        if !self.persistent || self.no_cache || self.disk_cache.lock().unwrap().is_some() {
+            return;
+        }
+        let handler = Arc::clone(self);
+        let url = url.to_owned();
+        thread::spawn(move || {
+            let uuid = svn_helper::run_cancellable(
+                handler.login().as_ref(),
+                &["info", "--show-item", "repos-uuid", &url],
+                &CancelToken::default(),
+                Some(QUICK_TIMEOUT),
+            );
+            match uuid {
+                Ok(uuid) => {
+                    let disk_cache = DiskCache::open(&uuid).map(Arc::new);
+                    let mut locked = handler.disk_cache.lock().unwrap();
+                    if locked.is_none() {
+                        *locked = disk_cache;
+                    }
+                }
+                Err(e) => debug!("no on-disk cache, repository uuid unknown: {e}"),
             }
         });
-        self.thread_ids
+    }
+
+    pub(crate) fn login(&self) -> Option<Login> {
+        self.login.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_login(&self, login: Option<Login>) {
+        *self.login.lock().unwrap() = login;
+    }
+
+    /// drops the cached response, so the next request goes to the repository
+    pub(crate) fn forget(&self, req: &DataRequest) {
+        self.cache.lock().unwrap().remove(req);
+        if let (Some(disk_cache), Some(key)) = (
+            &*self.disk_cache.lock().unwrap(),
+            req.disk_key(self.revision.as_deref()),
+        ) {
+            disk_cache.remove(&key);
+        }
+    }
+
+    /// drops the cached responses of urls starting with `prefix`, or all of them;
+    /// returns how many entries were removed from memory and from disk
+    pub(crate) fn clear_cache(&self, prefix: Option<&str>) -> (usize, usize) {
+        let mut locked = self.cache.lock().unwrap();
+        let before = locked.len();
+        locked.retain(|req, _| {
+            prefix.is_some_and(|p| !String::from(TargetUrl::from(req.clone())).starts_with(p))
+        });
+        let in_memory = before - locked.len();
+        let on_disk = match &*self.disk_cache.lock().unwrap() {
+            Some(disk_cache) => disk_cache.clear(prefix),
+            None => 0,
+        };
+        (in_memory, on_disk)
+    }
+
+    pub(crate) fn cache_stats(&self) -> CacheStats {
+        let locked = self.cache.lock().unwrap();
+        let memory_bytes = locked
+            .values()
+            .map(|e| serde_json::to_vec(&e.response).map_or(0, |v| v.len()))
+            .sum();
+        let oldest = locked
+            .iter()
+            .min_by_key(|(_, e)| e.checked)
+            .map(|(req, e)| (req.to_string(), e.checked));
+        CacheStats {
+            entries: locked.len(),
+            memory_bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            oldest,
+            disk: self.disk_cache.lock().unwrap().as_ref().map(|d| d.stats()),
+        }
+    }
+
+    /// the cached response shown for this request may be replaced by a fresh one
+    pub(crate) fn is_revalidating(&self, req: &DataRequest) -> bool {
+        self.revalidating.lock().unwrap().contains(req)
+    }
+
+    /// running requests, oldest first, then the queued ones in the order they'll run
+    pub(crate) fn activity(&self) -> Vec<Activity> {
+        let revalidating = self.revalidating.lock().unwrap();
+        let retrying = self.retrying.lock().unwrap();
+        let mut running: Vec<Activity> = self
+            .running
             .lock()
             .unwrap()
-            .insert(view_id, (id, Box::new(f)));
+            .values()
+            .map(|(req, view_id, since)| Activity {
+                state: match retrying.get(req) {
+                    Some(attempt) => ActivityState::Retrying(*attempt),
+                    None if revalidating.contains(req) => ActivityState::Revalidating,
+                    None => ActivityState::Running,
+                },
+                req: req.clone(),
+                view_id: *view_id,
+                since: *since,
+            })
+            .collect();
+        running.sort_by_key(|a| a.since);
+        // queued prefetches aren't listed, they come and go with every move of the selection
+        let queue = self.queue.lock().unwrap();
+        let queued = queue
+            .jobs
+            .iter()
+            .filter(|job| !job.cancel.is_cancelled())
+            .map(|job| Activity {
+                req: job.req.clone(),
+                view_id: job.view_id,
+                state: ActivityState::Queued,
+                since: job.queued,
+            });
+        running.extend(queued);
+        running
+    }
+
+    /// request waiting for another attempt, for the messages pane
+    pub(crate) fn retrying(&self) -> Option<String> {
+        let retrying = self.retrying.lock().unwrap();
+        let (req, attempt) = retrying.iter().next()?;
+        Some(format!("retrying {req} ({attempt}/{MAX_ATTEMPTS})…"))
     }
 
-    fn create_fetcher<F>(self: Arc<Self>, req: DataRequest, mut cb: F) -> ThreadId
+    /// queues the request; an older request of the same view is cancelled
+    pub(crate) fn request<F>(self: Arc<Self>, req: DataRequest, view_id: ViewId, f: F)
     where
-        F: FnMut(ResultDataResponse, ThreadId) + Send + 'static,
+        F: FnMut(ResultDataResponse) + Send + 'static,
     {
-        let join_h = thread::spawn(move || {
-            let res_resp = self.get_cached(req);
-            let cur_id = thread::current().id();
-            debug!("current thread id: {cur_id:?}");
-            (cb)(res_resp, cur_id);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancel = CancelToken::default();
+        let superseded = self.pending.lock().unwrap().insert(
+            view_id,
+            Pending {
+                id,
+                cancel: cancel.clone(),
+                cb: Box::new(f),
+            },
+        );
+        if let Some(superseded) = superseded {
+            debug!("request {} superseded by {id}", superseded.id);
+            superseded.cancel.cancel();
+        }
+        self.start_workers();
+        self.queue.lock().unwrap().jobs.push_back(Job {
+            id,
+            req,
+            view_id,
+            cancel,
+            queued: Instant::now(),
         });
-        let id = join_h.thread().id();
-        debug!("thread id: {id:?}");
-        id
+        self.queue_ready.notify_one();
+    }
+
+    /// cancels the request of one view, queued or running
+    pub(crate) fn cancel(&self, view_id: ViewId) {
+        if let Some(p) = self.pending.lock().unwrap().remove(&view_id) {
+            debug!("request {} cancelled", p.id);
+            p.cancel.cancel();
+        }
+    }
+
+    /// cancels every queued and running request; returns how many there were
+    pub(crate) fn cancel_all(&self) -> usize {
+        let mut pending = self.pending.lock().unwrap();
+        for p in pending.values() {
+            p.cancel.cancel();
+        }
+        let n = pending.len();
+        pending.clear();
+        self.prefetch_cancel.lock().unwrap().cancel();
+        self.queue.lock().unwrap().prefetch.clear();
+        n
+    }
+
+    /// lists the selected directory, and its neighbours if configured, before they are entered;
+    /// the prefetches of the previous selection are dropped
+    pub(crate) fn prefetch(
+        self: &Arc<Self>,
+        base_url: &str,
+        entries: &[ListEntry],
+        selected: usize,
+    ) {
+        if self.no_cache || self.prefetch.depth == 0 {
+            return;
+        }
+        let cancel = CancelToken::default();
+        std::mem::replace(&mut *self.prefetch_cancel.lock().unwrap(), cancel.clone()).cancel();
+        let siblings = self.prefetch.siblings as isize;
+        // the selected entry first, then the nearer neighbours before the farther ones
+        let around = (1..=siblings).flat_map(|d| [d, -d]);
+        let dirs: Vec<String> = std::iter::once(0)
+            .chain(around)
+            .filter_map(|d| entries.get(selected.checked_add_signed(d)?))
+            .filter(|e| e.kind == PathType::Dir)
+            .map(|e| format!("{base_url}{}/", e.name))
+            .collect();
+        debug!("prefetching {dirs:?}");
+        self.start_workers();
+        let mut queue = self.queue.lock().unwrap();
+        queue.prefetch.clear();
+        queue
+            .prefetch
+            .extend(dirs.into_iter().map(|url| PrefetchJob {
+                url,
+                depth: self.prefetch.depth,
+                cancel: cancel.clone(),
+            }));
+        drop(queue);
+        self.queue_ready.notify_all();
+    }
+
+    fn start_workers(self: &Arc<Self>) {
+        if self.workers_started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        for n in 0..WORKERS {
+            let dh = Arc::clone(self);
+            thread::Builder::new()
+                .name(format!("svn-worker-{n}"))
+                .spawn(move || dh.work())
+                .expect("failed to spawn a worker thread");
+        }
     }
 
-    fn get_cached(self: Arc<Self>, req: DataRequest) -> ResultDataResponse {
-        {
-            let locked = self.cache.lock().unwrap();
-            if let Some((resp, sys_time)) = locked.get(&req) {
-                if SystemTime::now().duration_since(*sys_time)? < MAX_VALIDITY_OF_CACHED_LIST {
-                    return Ok(resp.clone());
+    /// the next request, or a prefetch if none is waiting and the prefetch limit allows it
+    fn next_work(&self) -> Work {
+        // one worker always stays free for requests
+        let limit = self.prefetch.concurrency.min(WORKERS - 1);
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if let Some(job) = queue.jobs.pop_front() {
+                return Work::Request(job);
+            }
+            if self.prefetching.load(Ordering::Relaxed) < limit {
+                if let Some(job) = queue.prefetch.pop_front() {
+                    self.prefetching.fetch_add(1, Ordering::Relaxed);
+                    return Work::Prefetch(job);
                 }
+            }
+            queue = self.queue_ready.wait(queue).unwrap();
+        }
+    }
+
+    fn work(&self) {
+        loop {
+            let job = match self.next_work() {
+                Work::Request(job) => job,
+                Work::Prefetch(job) => {
+                    self.prefetch_dir(job);
+                    self.prefetching.fetch_sub(1, Ordering::Relaxed);
+                    self.queue_ready.notify_one();
+                    continue;
+                }
+            };
+            if job.cancel.is_cancelled() {
+                debug!("skipping cancelled request {}", job.id);
+                continue;
+            }
+            debug!("request {} started: {}", job.id, job.req);
+            self.running
+                .lock()
+                .unwrap()
+                .insert(job.id, (job.req.clone(), job.view_id, Instant::now()));
+            self.get_cached(job.req, &job.cancel, &mut |res_resp| {
+                self.deliver(job.view_id, job.id, res_resp)
+            });
+            self.running.lock().unwrap().remove(&job.id);
+            let mut pending = self.pending.lock().unwrap();
+            if pending.get(&job.view_id).is_some_and(|p| p.id == job.id) {
+                pending.remove(&job.view_id);
+            }
+        }
+    }
+
+    /// lists a directory into the cache, and queues its subdirectories while depth is left
+    fn prefetch_dir(&self, job: PrefetchJob) {
+        if job.cancel.is_cancelled() {
+            return;
+        }
+        let req = DataRequest::List(TargetUrl(job.url.clone()));
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&req)
+            .map(|e| e.response.clone());
+        let response = match cached {
+            Some(response) => Some(response),
+            None => {
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                self.running
+                    .lock()
+                    .unwrap()
+                    .insert(id, (req.clone(), ViewId::Prefetch, Instant::now()));
+                let mut fetched = None;
+                self.get_cached(req, &job.cancel, &mut |res_resp| {
+                    fetched = res_resp.ok().map(DataResponse::into_inner);
+                });
+                self.running.lock().unwrap().remove(&id);
+                fetched
+            }
+        };
+        let Some(DataResponse::List(entries)) = response else {
+            return;
+        };
+        if job.depth <= 1 || job.cancel.is_cancelled() {
+            return;
+        }
+        let children = entries
+            .iter()
+            .filter(|e| e.kind == PathType::Dir)
+            .map(|e| PrefetchJob {
+                url: format!("{}{}/", job.url, e.name),
+                depth: job.depth - 1,
+                cancel: job.cancel.clone(),
+            });
+        self.queue.lock().unwrap().prefetch.extend(children);
+        self.queue_ready.notify_all();
+    }
+
+    /// hands the response to the view, unless a newer request took its place
+    fn deliver(&self, view_id: ViewId, id: u64, res_resp: ResultDataResponse) {
+        if matches!(res_resp, Err(CustomError::Cancelled)) {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(p) = pending.get_mut(&view_id).filter(|p| p.id == id) {
+            (p.cb)(res_resp);
+        }
+    }
+
+    /// hands the response to `deliver`
+    ///
+    /// a cached HEAD entry of a list, file or log is delivered at once and checked afterwards;
+    /// if it turns out to be stale, the fresh response is delivered as well, as `Refreshed`.
+    fn get_cached(
+        &self,
+        req: DataRequest,
+        cancel: &CancelToken,
+        deliver: &mut dyn FnMut(ResultDataResponse),
+    ) {
+        if self.no_cache || !req.cacheable() {
+            let res_resp = self.fetch(&req, cancel, deliver);
+            deliver(res_resp);
+            return;
+        }
+        let immutable = req.immutable(self.revision.as_deref());
+        let disk_cache = self.disk_cache.lock().unwrap().clone();
+        let disk_key = disk_cache
+            .as_ref()
+            .and_then(|_| req.disk_key(self.revision.as_deref()));
+        let url = String::from(TargetUrl::from(req.clone()));
+        let store = |entry: &CacheEntry| {
+            if let (Some(disk_cache), Some(key)) = (&disk_cache, &disk_key) {
+                disk_cache.store(key, &url, entry);
+            }
+            let mut locked = self.cache.lock().unwrap();
+            locked.insert(req.clone(), entry.clone());
+        };
+        let mut cached = self.cache.lock().unwrap().get(&req).cloned();
+        if let (None, Some(disk_cache), Some(key)) = (&cached, &disk_cache, &disk_key) {
+            cached = disk_cache.load(key);
+            if let Some(entry) = &cached {
+                debug!("loaded {key} from disk");
+                let mut locked = self.cache.lock().unwrap();
+                locked.insert(req.clone(), entry.clone());
+            }
+        }
+        let Some(mut entry) = cached else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            let revision = if immutable {
+                None
+            } else {
+                self.last_changed(&req, cancel)
             };
+            let res_resp = self.fetch(&req, cancel, deliver);
+            if let Ok(response) = &res_resp {
+                store(&CacheEntry::new(response.clone(), revision));
+            }
+            deliver(res_resp);
+            return;
+        };
+        if immutable || entry.recently_checked() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            deliver(Ok(entry.response));
+            return;
+        }
+        // a second delivery would duplicate log pages and reset the blame cursor
+        let background = matches!(
+            req,
+            DataRequest::List(_)
+                | DataRequest::Tree(_)
+                | DataRequest::Text(_)
+                | DataRequest::Log(_)
+        );
+        if background {
+            self.revalidating.lock().unwrap().insert(req.clone());
+            deliver(Ok(entry.response.clone()));
+        }
+        let revision = self.last_changed(&req, cancel);
+        let res_resp = if revision.is_some() && revision == entry.revision {
+            debug!("{req} is still up to date");
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            entry.checked = SystemTime::now();
+            store(&entry);
+            (!background).then_some(Ok(entry.response))
+        } else {
+            debug!(
+                "{req} changed since r{:?}, fetching it again",
+                entry.revision
+            );
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            // the cached copy stays on screen until the fresh one is complete
+            let res_resp = self.fetch(&req, cancel, &mut |_| {});
+            if let Ok(response) = &res_resp {
+                store(&CacheEntry::new(response.clone(), revision));
+            }
+            match res_resp {
+                Ok(response) if background => Some(Ok(DataResponse::Refreshed(Box::new(response)))),
+                res_resp => Some(res_resp),
+            }
+        };
+        self.revalidating.lock().unwrap().remove(&req);
+        if let Some(res_resp) = res_resp {
+            deliver(res_resp);
+        }
+    }
+
+    /// last-changed revision of the request's url, used to tell whether a cached response is stale
+    fn last_changed(&self, req: &DataRequest, cancel: &CancelToken) -> Option<u32> {
+        let url = self.pegged(&String::from(TargetUrl::from(req.clone())));
+        svn_helper::run_cancellable(
+            self.login().as_ref(),
+            &["info", "--show-item", "last-changed-revision", &url],
+            cancel,
+            Some(QUICK_TIMEOUT),
+        )
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+    }
+
+    /// runs svn for the request, without looking at the cache; transient errors are retried.
+    /// parts of a response that comes in bit by bit go to `progress` on the way
+    fn fetch(
+        &self,
+        req: &DataRequest,
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(ResultDataResponse),
+    ) -> ResultDataResponse {
+        let mut attempt = 1;
+        let mut backoff = FIRST_BACKOFF;
+        loop {
+            let res_resp = self.fetch_once(req, cancel, progress);
+            match &res_resp {
+                Err(e) if e.is_transient() && req.retryable() && attempt < MAX_ATTEMPTS => {
+                    attempt += 1;
+                    debug!("{req} failed ({e}), attempt {attempt} in {backoff:?}");
+                    // shown until the retried request is done, not only during the wait
+                    self.retrying.lock().unwrap().insert(req.clone(), attempt);
+                    if !wait(backoff, cancel) {
+                        self.retrying.lock().unwrap().remove(req);
+                        return Err(CustomError::Cancelled);
+                    }
+                    backoff *= 2;
+                }
+                _ => {
+                    if attempt > 1 {
+                        self.retrying.lock().unwrap().remove(req);
+                    }
+                    return res_resp;
+                }
+            }
         }
-        let cmd = svn_helper::new();
-        let int_ret: ResultDataResponse = match &req {
+    }
+
+    fn fetch_once(
+        &self,
+        req: &DataRequest,
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(ResultDataResponse),
+    ) -> ResultDataResponse {
+        let login = self.login();
+        let timeout = req.timeout();
+        let run =
+            |args: &[&str]| svn_helper::run_cancellable(login.as_ref(), args, cancel, timeout);
+        match req {
             DataRequest::List(TargetUrl(url)) => {
+                let url = &self.pegged(url);
                 debug!("list requested for {url}");
-                let list = cmd
-                    .list(url, false)
-                    .map_or_else(|e| Err(e.into()), |v| Ok(v.into()));
+                let list =
+                    svn_helper::list(login.as_ref(), url, cancel, timeout).map(DataResponse::from);
                 debug!("got list");
                 list
             }
-            DataRequest::Log(TargetUrl(url)) => cmd
-                .log(url)
-                .map_or_else(|e| Err(e.into()), |v| Ok(v.into())),
-            DataRequest::Info(TargetUrl(url)) => cmd
-                .info(url)
-                .map_or_else(|e| Err(e.into()), |v| Ok(v.into())),
-            DataRequest::Text(TargetUrl(url)) => cmd
-                .cat(url)
-                .map_or_else(|e| Err(e.into()), |v| Ok(v.into())),
-        };
-        if let Ok(resp) = &int_ret {
-            let mut locked = self.cache.lock().unwrap();
-            locked.insert(req, (resp.clone(), SystemTime::now()));
+            DataRequest::Tree(TargetUrl(url)) => {
+                let mut entries = vec![];
+                let mut sent = 0;
+                let mut last_sent = Instant::now();
+                svn_helper::list_recursive(
+                    login.as_ref(),
+                    &self.pegged(url),
+                    cancel,
+                    timeout,
+                    &mut |entry| {
+                        entries.push(entry);
+                        if last_sent.elapsed() >= STREAM_INTERVAL {
+                            let part = entries[sent..].to_vec();
+                            progress(Ok(DataResponse::TreePart(sent, part)));
+                            sent = entries.len();
+                            last_sent = Instant::now();
+                        }
+                    },
+                )?;
+                debug!("got tree of {} entries", entries.len());
+                Ok(DataResponse::Tree(entries))
+            }
+            DataRequest::Log(TargetUrl(url)) => {
+                run(&["log", "-l", "1", &self.pegged(url)]).map(|text| revlog::parse(&text).into())
+            }
+            DataRequest::LogPage(TargetUrl(url), from) => {
+                let limit = LOG_PAGE_SIZE.to_string();
+                let range = from.map(|rev| format!("{rev}:1"));
+                let target = self.pegged(url);
+                let mut args = vec!["log", "-v", "-l", &limit];
+                if let Some(range) = &range {
+                    args.extend(["-r", range]);
+                }
+                args.push(&target);
+                run(&args).map(|text| revlog::parse(&text).into())
+            }
+            DataRequest::Text(TargetUrl(url)) => {
+                run(&["cat", &self.pegged(url)]).map(DataResponse::from)
+            }
+            DataRequest::Diff(TargetUrl(url), range) => {
+                let target = match range.peg() {
+                    Some(peg) => format!("{url}@{peg}"),
+                    None => url.clone(),
+                };
+                let args = range.args();
+                let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+                args.insert(0, "diff");
+                args.push(&target);
+                run(&args).map(|text| Diff::parse(&text).into())
+            }
+            DataRequest::Blame(TargetUrl(url), rev) => {
+                let target = match rev {
+                    Some(rev) => format!("{url}@{rev}"),
+                    None => self.pegged(url),
+                };
+                run(&["blame", "-v", &target]).map(|text| blame::parse(&text).into())
+            }
+            DataRequest::Status(TargetUrl(path), incoming) => {
+                let mut args = vec!["status", path.as_str()];
+                if *incoming {
+                    args.push("-u");
+                }
+                run(&args).map(|text| status::parse(&text, *incoming).into())
+            }
+            DataRequest::BaseDiff(paths) => {
+                let targets = wc_targets(paths.iter());
+                let mut args = vec!["diff", "--"];
+                args.extend(targets.iter().map(String::as_str));
+                run(&args).map(|text| Diff::parse(&text).into())
+            }
+            DataRequest::Commit(paths, msg, deleted_dirs) => {
+                // the message goes through a file, so it survives any quoting and newlines
+                let mut msg_file = tempfile::NamedTempFile::new()?;
+                msg_file.write_all(msg.as_bytes())?;
+                let msg_path = msg_file.path().to_string_lossy().into_owned();
+                let commit = |depth: &str, targets: Vec<String>| {
+                    let mut args = vec!["commit", "--depth", depth, "-F", &msg_path, "--"];
+                    args.extend(targets.iter().map(String::as_str));
+                    run(&args)
+                };
+                // svn won't delete a directory at depth empty, and below a deleted directory
+                // there is nothing but deletions
+                let mut output = String::new();
+                if !deleted_dirs.is_empty() {
+                    debug!("committing deleted directories {deleted_dirs:?}");
+                    output = commit("infinity", wc_targets(deleted_dirs.iter()))?;
+                }
+                // depth empty: a marked directory doesn't drag in changes of unmarked children
+                let rest: Vec<&String> = paths
+                    .iter()
+                    .filter(|p| !deleted_dirs.iter().any(|d| Path::new(p).starts_with(d)))
+                    .collect();
+                if !rest.is_empty() {
+                    debug!("committing {rest:?}");
+                    output.push_str(&commit("empty", wc_targets(rest.into_iter()))?);
+                }
+                Ok(DataResponse::from(output))
+            }
+        }
+    }
+
+    /// applies the revision given on the command line as a peg revision
+    fn pegged(&self, url: &str) -> String {
+        match &self.revision {
+            Some(rev) => format!("{url}@{rev}"),
+            None => url.to_owned(),
+        }
+    }
+}
+
+/// working-copy paths as svn targets; the trailing '@' keeps an '@' in a path from being read
+/// as a peg revision
+fn wc_targets<'a>(paths: impl Iterator<Item = &'a String>) -> Vec<String> {
+    paths.map(|p| format!("{p}@")).collect()
+}
+
+/// sleeps for `duration`, unless cancelled first; true if it slept through
+fn wait(duration: Duration, cancel: &CancelToken) -> bool {
+    const STEP: Duration = Duration::from_millis(50);
+    let mut left = duration;
+    while !left.is_zero() {
+        if cancel.is_cancelled() {
+            return false;
         }
-        return int_ret;
+        let step = left.min(STEP);
+        thread::sleep(step);
+        left -= step;
     }
+    !cancel.is_cancelled()
 }
@@ -0,0 +1,518 @@
+use crate::theme::{self, Slot};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+/// word diffs of longer line pairs are skipped, the lcs table grows quadratically
+const MAX_WORD_DIFF_CELLS: usize = 20_000;
+
+/// revisions to compare in a `svn diff`
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub(crate) enum DiffRange {
+    /// `-c REV`: the change made by one revision
+    Change(u32),
+    /// `-r A:B`: everything between two revisions
+    Range(u32, u32),
+    /// local modifications of a working-copy path against BASE
+    Base,
+    /// `-r BASE:HEAD`: changes waiting to be pulled in by `svn update`
+    Incoming,
+}
+
+impl DiffRange {
+    pub(crate) fn args(&self) -> Vec<String> {
+        match self {
+            DiffRange::Change(rev) => vec!["-c".to_owned(), rev.to_string()],
+            DiffRange::Range(from, to) => vec!["-r".to_owned(), format!("{from}:{to}")],
+            DiffRange::Base => vec![],
+            DiffRange::Incoming => vec!["-r".to_owned(), "BASE:HEAD".to_owned()],
+        }
+    }
+
+    /// revision a url has to be pegged at, so deleted paths can still be diffed
+    pub(crate) fn peg(&self) -> Option<u32> {
+        match self {
+            DiffRange::Change(rev) => Some(*rev),
+            DiffRange::Range(from, to) => Some(*from.max(to)),
+            DiffRange::Base | DiffRange::Incoming => None,
+        }
+    }
+}
+
+impl fmt::Display for DiffRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffRange::Change(rev) => write!(f, "diff -c {rev}"),
+            DiffRange::Range(from, to) => write!(f, "diff -r {from}:{to}"),
+            DiffRange::Base => write!(f, "diff BASE"),
+            DiffRange::Incoming => write!(f, "diff -r BASE:HEAD"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum LineKind {
+    FileHeader,
+    Header,
+    Hunk,
+    Added,
+    Removed,
+    Context,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DiffLine {
+    pub(crate) kind: LineKind,
+    pub(crate) text: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum DiffMode {
+    Unified,
+    SideBySide,
+}
+
+pub(crate) enum DiffAction {
+    NextHunk,
+    PrevHunk,
+    NextFile,
+    PrevFile,
+    ToggleMode,
+}
+
+/// rows of hunk and file headers in one rendering of a diff
+#[derive(Clone, Debug, Default)]
+struct Marks {
+    hunks: Vec<usize>,
+    files: Vec<usize>,
+}
+
+/// old content on the left, new on the right, with one row per line of either side
+#[derive(Clone, Debug, Default)]
+struct SideBySide {
+    left: Vec<Spans<'static>>,
+    right: Vec<Spans<'static>>,
+    marks: Marks,
+}
+
+/// parsed unified diff, along with its side-by-side rendering
+///
+/// stored as the plain diff text, the renderings are rebuilt when loaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub(crate) struct Diff {
+    lines: Vec<DiffLine>,
+    marks: Marks,
+    side_by_side: SideBySide,
+    mode: DiffMode,
+}
+
+impl Diff {
+    pub(crate) fn parse(text: &str) -> Self {
+        let mut lines = vec![];
+        let mut marks = Marks::default();
+        let mut in_header = false;
+        for (n, line) in text.lines().enumerate() {
+            let kind = if line.starts_with("Index: ") {
+                marks.files.push(n);
+                in_header = true;
+                LineKind::FileHeader
+            } else if line.starts_with("@@") {
+                marks.hunks.push(n);
+                in_header = false;
+                LineKind::Hunk
+            } else if in_header {
+                LineKind::Header
+            } else if line.starts_with('+') {
+                LineKind::Added
+            } else if line.starts_with('-') {
+                LineKind::Removed
+            } else {
+                LineKind::Context
+            };
+            lines.push(DiffLine {
+                kind,
+                text: line.to_owned(),
+            });
+        }
+        let side_by_side = SideBySide::from(lines.as_slice());
+        Self {
+            lines,
+            marks,
+            side_by_side,
+            mode: DiffMode::Unified,
+        }
+    }
+
+    /// number of rows in the current mode
+    pub(crate) fn rows(&self) -> usize {
+        match self.mode {
+            DiffMode::Unified => self.lines.len(),
+            DiffMode::SideBySide => self.side_by_side.left.len(),
+        }
+    }
+
+    fn marks(&self) -> &Marks {
+        match self.mode {
+            DiffMode::Unified => &self.marks,
+            DiffMode::SideBySide => &self.side_by_side.marks,
+        }
+    }
+
+    /// row to scroll to after the action, starting from the current top row
+    ///
+    /// switching modes keeps the hunk at the top of the view.
+    pub(crate) fn apply(&mut self, from: usize, action: DiffAction) -> Option<usize> {
+        let marks = self.marks();
+        let (marks, forward) = match action {
+            DiffAction::NextHunk => (&marks.hunks, true),
+            DiffAction::PrevHunk => (&marks.hunks, false),
+            DiffAction::NextFile => (&marks.files, true),
+            DiffAction::PrevFile => (&marks.files, false),
+            DiffAction::ToggleMode => {
+                let hunk = marks.hunks.iter().rposition(|&m| m <= from);
+                self.mode = match self.mode {
+                    DiffMode::Unified => DiffMode::SideBySide,
+                    DiffMode::SideBySide => DiffMode::Unified,
+                };
+                return Some(hunk.map_or(0, |h| self.marks().hunks[h]));
+            }
+        };
+        if forward {
+            marks.iter().find(|&&m| m > from).copied()
+        } else {
+            marks.iter().rev().find(|&&m| m < from).copied()
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(
+        &self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        block: Block,
+        scroll: usize,
+    ) {
+        // only the rows on screen are built, a u16 scroll offset wouldn't reach far into long diffs
+        let height = usize::from(block.inner(area).height);
+        let visible = |rows: &[Spans<'static>]| -> Vec<Spans<'static>> {
+            rows.iter().skip(scroll).take(height).cloned().collect()
+        };
+        match self.mode {
+            DiffMode::Unified => {
+                let text: Vec<Spans> = self
+                    .lines
+                    .iter()
+                    .skip(scroll)
+                    .take(height)
+                    .map(|l| Spans::from(Span::styled(l.text.clone(), l.kind.style())))
+                    .collect();
+                frame.render_widget(Paragraph::new(text).block(block), area);
+            }
+            DiffMode::SideBySide => {
+                let inner = block.inner(area);
+                frame.render_widget(block, area);
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(inner);
+                frame.render_widget(
+                    Paragraph::new(visible(&self.side_by_side.left))
+                        .block(Block::default().borders(Borders::RIGHT)),
+                    columns[0],
+                );
+                frame.render_widget(
+                    Paragraph::new(visible(&self.side_by_side.right)),
+                    columns[1],
+                );
+            }
+        }
+    }
+}
+
+impl From<Diff> for String {
+    fn from(d: Diff) -> Self {
+        d.lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl From<String> for Diff {
+    fn from(text: String) -> Self {
+        Diff::parse(&text)
+    }
+}
+
+impl From<&[DiffLine]> for SideBySide {
+    fn from(lines: &[DiffLine]) -> Self {
+        let mut sbs = SideBySide::default();
+        let mut removed: Vec<&str> = vec![];
+        let mut added: Vec<&str> = vec![];
+        for line in lines {
+            match line.kind {
+                LineKind::Removed => {
+                    if !added.is_empty() {
+                        sbs.push_change(&mut removed, &mut added);
+                    }
+                    removed.push(&line.text[1..]);
+                }
+                LineKind::Added => added.push(&line.text[1..]),
+                kind => {
+                    sbs.push_change(&mut removed, &mut added);
+                    let row = sbs.left.len();
+                    match kind {
+                        LineKind::FileHeader => sbs.marks.files.push(row),
+                        LineKind::Hunk => sbs.marks.hunks.push(row),
+                        _ => {}
+                    }
+                    let text = match kind {
+                        LineKind::Context => line.text.get(1..).unwrap_or_default(),
+                        _ => &line.text,
+                    };
+                    let spans = Spans::from(Span::styled(text.to_owned(), kind.style()));
+                    sbs.left.push(spans.clone());
+                    sbs.right.push(spans);
+                }
+            }
+        }
+        sbs.push_change(&mut removed, &mut added);
+        sbs
+    }
+}
+
+impl SideBySide {
+    /// pairs removed with added lines row by row, highlighting the words that differ
+    fn push_change(&mut self, removed: &mut Vec<&str>, added: &mut Vec<&str>) {
+        for i in 0..removed.len().max(added.len()) {
+            match (removed.get(i), added.get(i)) {
+                (Some(old), Some(new)) => {
+                    let (left, right) = word_diff(old, new);
+                    self.left.push(left);
+                    self.right.push(right);
+                }
+                (old, new) => {
+                    self.left.push(whole_line(old, LineKind::Removed));
+                    self.right.push(whole_line(new, LineKind::Added));
+                }
+            }
+        }
+        removed.clear();
+        added.clear();
+    }
+}
+
+fn whole_line(line: Option<&&str>, kind: LineKind) -> Spans<'static> {
+    line.map_or_else(Spans::default, |l| {
+        Spans::from(Span::styled(l.to_string(), kind.style()))
+    })
+}
+
+/// splits a line into runs of word characters, whitespace and single punctuation characters
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut prev = None;
+    for (i, c) in line.char_indices() {
+        let cur = class(c);
+        if i > start && (prev != Some(cur) || cur == 2) {
+            tokens.push(&line[start..i]);
+            start = i;
+        }
+        prev = Some(cur);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// highlights the tokens of `old` and `new` that are not part of their longest common subsequence
+fn word_diff(old: &str, new: &str) -> (Spans<'static>, Spans<'static>) {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+    if n * m > MAX_WORD_DIFF_CELLS {
+        return (
+            whole_line(Some(&old), LineKind::Removed),
+            whole_line(Some(&new), LineKind::Added),
+        );
+    }
+    let mut lcs = vec![vec![0u16; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_tokens[i] == new_tokens[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut old_common = vec![false; n];
+    let mut new_common = vec![false; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_tokens[i] == new_tokens[j] {
+            old_common[i] = true;
+            new_common[j] = true;
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    let changed = |bg: Color| {
+        Style::default()
+            .fg(theme::color(Slot::DiffWordText))
+            .bg(bg)
+            .add_modifier(Modifier::BOLD)
+    };
+    let to_spans = |tokens: &[&str], common: &[bool], kind: LineKind, bg: Color| {
+        Spans::from(
+            tokens
+                .iter()
+                .zip(common)
+                .map(|(t, &c)| {
+                    let style = if c { kind.style() } else { changed(bg) };
+                    Span::styled(t.to_string(), style)
+                })
+                .collect::<Vec<_>>(),
+        )
+    };
+    (
+        to_spans(
+            &old_tokens,
+            &old_common,
+            LineKind::Removed,
+            theme::color(Slot::DiffRemoveWord),
+        ),
+        to_spans(
+            &new_tokens,
+            &new_common,
+            LineKind::Added,
+            theme::color(Slot::DiffAddWord),
+        ),
+    )
+}
+
+impl LineKind {
+    pub(crate) fn style(&self) -> Style {
+        match self {
+            LineKind::FileHeader => Style::default()
+                .fg(theme::color(Slot::DiffFile))
+                .add_modifier(Modifier::BOLD),
+            LineKind::Header => Style::default().add_modifier(Modifier::BOLD),
+            LineKind::Hunk => theme::fg(Slot::DiffHunk),
+            LineKind::Added => theme::fg(Slot::DiffAdd),
+            LineKind::Removed => theme::fg(Slot::DiffRemove),
+            LineKind::Context => Style::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+Index: a.txt
+===================================================================
+--- a.txt\t(revision 1)
++++ a.txt\t(revision 2)
+@@ -1,2 +1,2 @@
+-one
++two
+ three
+@@ -10 +10 @@
+-ten
+Index: b.txt
+===================================================================
+@@ -0,0 +1 @@
++new";
+
+    #[test]
+    fn parse_kinds_and_marks() {
+        let diff = Diff::parse(DIFF);
+        let kinds: Vec<LineKind> = diff.lines.iter().map(|l| l.kind).collect();
+        assert_eq!(kinds[0], LineKind::FileHeader);
+        assert_eq!(kinds[1..4], [LineKind::Header; 3]);
+        assert_eq!(
+            kinds[4..8],
+            [
+                LineKind::Hunk,
+                LineKind::Removed,
+                LineKind::Added,
+                LineKind::Context
+            ]
+        );
+        assert_eq!(diff.marks.files, [0, 10]);
+        assert_eq!(diff.marks.hunks, [4, 8, 12]);
+    }
+
+    #[test]
+    fn apply_moves_between_marks() {
+        let mut diff = Diff::parse(DIFF);
+        assert_eq!(diff.apply(0, DiffAction::NextHunk), Some(4));
+        assert_eq!(diff.apply(4, DiffAction::NextHunk), Some(8));
+        assert_eq!(diff.apply(12, DiffAction::NextHunk), None);
+        assert_eq!(diff.apply(9, DiffAction::PrevHunk), Some(8));
+        assert_eq!(diff.apply(0, DiffAction::PrevHunk), None);
+        assert_eq!(diff.apply(5, DiffAction::NextFile), Some(10));
+        assert_eq!(diff.apply(10, DiffAction::PrevFile), Some(0));
+    }
+
+    /// the texts of the spans in bold, which are the changed tokens
+    fn changed(spans: &Spans) -> Vec<String> {
+        spans
+            .0
+            .iter()
+            .filter(|s| s.style.add_modifier.contains(Modifier::BOLD))
+            .map(|s| s.content.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_words_spaces_and_punctuation() {
+        assert_eq!(
+            tokenize("let x_1 = f(a, b);"),
+            ["let", " ", "x_1", " ", "=", " ", "f", "(", "a", ",", " ", "b", ")", ";"]
+        );
+        assert_eq!(tokenize("  ::"), ["  ", ":", ":"]);
+        assert!(tokenize("").is_empty());
+    }
+
+    #[test]
+    fn word_diff_highlights_changed_tokens() {
+        let (old, new) = word_diff("let x = foo(a);", "let y = foo(a, b);");
+        assert_eq!(changed(&old), ["x"]);
+        assert_eq!(changed(&new), ["y", ",", " ", "b"]);
+        let all: String = new.0.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(all, "let y = foo(a, b);");
+    }
+
+    #[test]
+    fn word_diff_of_equal_lines_changes_nothing() {
+        let (old, new) = word_diff("same line", "same line");
+        assert!(changed(&old).is_empty());
+        assert!(changed(&new).is_empty());
+    }
+}
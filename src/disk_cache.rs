@@ -0,0 +1,195 @@
+use crate::{data_handler::CacheEntry, CustomError};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+const APP_DIR: &str = "svn-tui";
+/// entries not used for this long are dropped, of whichever repository
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// beyond this, all repositories together, the least recently used entries are dropped
+const MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// one response as stored on disk; the key guards against hash collisions
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    key: String,
+    /// url of the request, to clear subtrees
+    url: String,
+    entry: CacheEntry,
+}
+
+/// responses of one repository, one json file per request under the xdg cache dir
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// cache dir of the repository with this uuid, created if needed
+    pub(crate) fn open(uuid: &str) -> Option<Self> {
+        let uuid = uuid.trim();
+        if uuid.is_empty() || !uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            debug!("not a repository uuid: '{uuid}'");
+            return None;
+        }
+        let root = dirs::cache_dir()?.join(APP_DIR);
+        evict(&root);
+        let dir = root.join(uuid);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            debug!("can't create cache dir {dir:?}: {e}");
+            return None;
+        }
+        debug!("on-disk cache at {dir:?}");
+        Some(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(key)))
+    }
+
+    pub(crate) fn load(&self, key: &str) -> Option<CacheEntry> {
+        let path = self.path(key);
+        let file = File::open(&path).ok()?;
+        match serde_json::from_reader::<_, StoredEntry>(BufReader::new(file)) {
+            Ok(stored) if stored.key == key => {
+                // the modification time tells eviction when the entry was last used
+                let _ = File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|f| f.set_modified(SystemTime::now()));
+                Some(stored.entry)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                debug!("dropping unreadable cache entry for {key}: {e}");
+                let _ = fs::remove_file(self.path(key));
+                None
+            }
+        }
+    }
+
+    /// failures are only logged, the cache is never worth an error in the ui
+    pub(crate) fn store(&self, key: &str, url: &str, entry: &CacheEntry) {
+        if let Err(e) = self.try_store(key, url, entry) {
+            debug!("can't store cache entry for {key}: {e}");
+        }
+    }
+
+    fn try_store(&self, key: &str, url: &str, entry: &CacheEntry) -> Result<(), CustomError> {
+        let stored = StoredEntry {
+            key: key.to_owned(),
+            url: url.to_owned(),
+            entry: entry.clone(),
+        };
+        // written to a temporary file first, so a crash never leaves half an entry behind
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        serde_json::to_writer(BufWriter::new(&mut tmp), &stored)?;
+        tmp.persist(self.path(key)).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+
+    fn entries(&self) -> Vec<PathBuf> {
+        fs::read_dir(&self.dir)
+            .map(|dir| {
+                dir.filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// number of entries and their total size in bytes
+    pub(crate) fn stats(&self) -> (usize, u64) {
+        let files = self.entries();
+        let bytes = files
+            .iter()
+            .filter_map(|p| fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+        (files.len(), bytes)
+    }
+
+    /// removes the entries of urls starting with `prefix`, or all of them; returns how many
+    pub(crate) fn clear(&self, prefix: Option<&str>) -> usize {
+        let mut removed = 0;
+        for path in self.entries() {
+            let matches = match prefix {
+                None => true,
+                Some(prefix) => File::open(&path)
+                    .ok()
+                    .and_then(|f| serde_json::from_reader::<_, StoredEntry>(BufReader::new(f)).ok())
+                    .is_none_or(|stored| stored.url.starts_with(prefix)),
+            };
+            if matches && fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        debug!("removed {removed} cache entries from disk");
+        removed
+    }
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` it stays the same across Rust releases, and so do the
+/// file names of the entries
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// drops the entries of every repository under `root` that weren't used for `MAX_AGE`, then the
+/// least recently used ones until the rest fits in `MAX_BYTES`
+fn evict(root: &Path) {
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .filter_map(|repo| fs::read_dir(repo.ok()?.path()).ok())
+        .flatten()
+        .filter_map(|e| {
+            let path = e.ok()?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                return None;
+            }
+            let meta = fs::metadata(&path).ok()?;
+            Some((meta.modified().ok()?, meta.len(), path))
+        })
+        .collect();
+    // the least recently used first
+    files.sort();
+    let now = SystemTime::now();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut removed = 0;
+    for (used, len, path) in files {
+        let expired = now.duration_since(used).is_ok_and(|age| age > MAX_AGE);
+        if !expired && total <= MAX_BYTES {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        debug!("evicted {removed} cache entries from disk");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_use_the_reference_fnv1a() {
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a("foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    data_handler::{DataRequest, ViewId},
+    keymap::{self, Action},
+    theme::{self, Slot},
+    ui::centered_rect,
+};
+use std::{collections::VecDeque, time::Instant};
+use tui::{
+    backend::Backend,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+/// oldest errors are dropped beyond this
+const MAX_HISTORY: usize = 200;
+
+/// a background fetch that failed, as kept in the error history
+pub(crate) struct FailedRequest {
+    pub(crate) req: DataRequest,
+    pub(crate) view_id: ViewId,
+    pub(crate) error: String,
+}
+
+impl FailedRequest {
+    /// commits are retried from the commit dialog, they must not be sent twice by accident
+    fn retryable(&self) -> bool {
+        self.req.retryable()
+    }
+}
+
+struct Record {
+    failed: FailedRequest,
+    at: Instant,
+}
+
+pub(crate) enum ErrorOutcome {
+    Browsing,
+    Close,
+    Retry(DataRequest, ViewId),
+}
+
+/// every failed request of the session, newest last
+#[derive(Default)]
+pub(crate) struct ErrorLog {
+    history: VecDeque<Record>,
+    /// the newest error is shown in the messages pane until the next key press
+    banner: bool,
+    /// selected row of the history popup, while it's open
+    popup: Option<usize>,
+}
+
+impl ErrorLog {
+    pub(crate) fn push(&mut self, failed: FailedRequest) {
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+            self.popup = self.popup.map(|s| s.saturating_sub(1));
+        }
+        self.history.push_back(Record {
+            failed,
+            at: Instant::now(),
+        });
+        self.banner = true;
+    }
+
+    pub(crate) fn dismiss(&mut self) {
+        self.banner = false;
+    }
+
+    pub(crate) fn banner(&self) -> Option<String> {
+        let last = self.history.back().filter(|_| self.banner)?;
+        let retry = if last.failed.retryable() {
+            format!("[retry: {}], ", keymap::key(Action::Retry))
+        } else {
+            String::new()
+        };
+        Some(format!(
+            "error: {}: {} {retry}[errors: {}]",
+            last.failed.req,
+            one_line(&last.failed.error),
+            keymap::key(Action::Errors)
+        ))
+    }
+
+    /// newest request that can be retried
+    pub(crate) fn last_retryable(&self) -> Option<(DataRequest, ViewId)> {
+        self.history
+            .iter()
+            .rev()
+            .find(|r| r.failed.retryable())
+            .map(|r| (r.failed.req.clone(), r.failed.view_id))
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.popup.is_some()
+    }
+
+    pub(crate) fn open(&mut self) {
+        self.popup = Some(self.history.len().saturating_sub(1));
+    }
+
+    pub(crate) fn handle(&mut self, action: Action) -> ErrorOutcome {
+        let last = self.history.len().saturating_sub(1);
+        let Some(selected) = self.popup.as_mut() else {
+            return ErrorOutcome::Close;
+        };
+        match action {
+            Action::Cancel | Action::Back | Action::Errors => {
+                self.popup = None;
+                return ErrorOutcome::Close;
+            }
+            Action::Down => *selected = (*selected + 1).min(last),
+            Action::Up => *selected = selected.saturating_sub(1),
+            Action::Open | Action::Retry => {
+                let retry = self
+                    .history
+                    .get(*selected)
+                    .filter(|r| r.failed.retryable())
+                    .map(|r| (r.failed.req.clone(), r.failed.view_id));
+                if let Some((req, view_id)) = retry {
+                    self.popup = None;
+                    return ErrorOutcome::Retry(req, view_id);
+                }
+            }
+            _ => {}
+        }
+        ErrorOutcome::Browsing
+    }
+
+    pub(crate) fn render<B: Backend>(&self, frame: &mut Frame<B>) {
+        let area = centered_rect(80, 60, frame.size());
+        let items: Vec<ListItem> = self
+            .history
+            .iter()
+            .map(|r| {
+                ListItem::new(vec![
+                    Spans::from(vec![
+                        Span::styled(format!("{:>8} ", ago(r.at)), theme::fg(Slot::Muted)),
+                        Span::styled(r.failed.req.to_string(), theme::fg(Slot::Accent)),
+                    ]),
+                    Spans::from(Span::styled(
+                        format!("         {}", one_line(&r.failed.error)),
+                        theme::fg(Slot::Error),
+                    )),
+                ])
+            })
+            .collect();
+        let mut title = format!(
+            "error history : [retry: {}], [close: {}]",
+            keymap::keys(&[Action::Open, Action::Retry]),
+            keymap::keys(&[Action::Cancel, Action::Back])
+        );
+        if items.is_empty() {
+            title.push_str(" (no errors)");
+        }
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAlert))
+                    .border_type(BorderType::Thick),
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .bg(theme::color(Slot::SelectedBg)),
+            )
+            .highlight_symbol(">>");
+        let mut state = ListState::default();
+        state.select(self.popup);
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+/// svn errors span several lines; the messages pane has room for one
+fn one_line(error: &str) -> String {
+    error
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn ago(at: Instant) -> String {
+    match at.elapsed().as_secs() {
+        s if s < 60 => format!("{s}s ago"),
+        s if s < 3600 => format!("{}m ago", s / 60),
+        s => format!("{}h ago", s / 3600),
+    }
+}
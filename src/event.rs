@@ -0,0 +1,45 @@
+use crate::data_handler::{DataRequest, ResultDataResponse, ViewId};
+use crossterm::event::{read, Event, KeyEvent};
+use log::debug;
+use std::{io, sync::mpsc::Sender, thread, time::Duration};
+
+/// drives the spinner and the debounced requests; nothing is drawn on a tick while idle
+const TICK: Duration = Duration::from_millis(200);
+
+/// everything the ui reacts to, in the order it happened
+pub(crate) enum AppEvent {
+    Key(KeyEvent),
+    Resize,
+    Tick,
+    /// a response of the data handler, for the request and view it was asked for
+    Response(DataRequest, ViewId, Box<ResultDataResponse>),
+    /// the terminal can't be read anymore; without keys there is no way to quit, so the ui ends
+    InputFailed(io::Error),
+}
+
+/// forwards terminal events; ends when the ui is gone or the terminal can't be read
+pub(crate) fn spawn_input(tx: Sender<AppEvent>) {
+    thread::spawn(move || loop {
+        let event = match read() {
+            Ok(Event::Key(key)) => AppEvent::Key(key),
+            Ok(Event::Resize(..)) => AppEvent::Resize,
+            Ok(_) => continue,
+            Err(e) => {
+                debug!("can't read terminal events: {e}");
+                let _ = tx.send(AppEvent::InputFailed(e));
+                return;
+            }
+        };
+        if tx.send(event).is_err() {
+            return;
+        }
+    });
+}
+
+pub(crate) fn spawn_ticks(tx: Sender<AppEvent>) {
+    thread::spawn(move || {
+        while tx.send(AppEvent::Tick).is_ok() {
+            thread::sleep(TICK);
+        }
+    });
+}
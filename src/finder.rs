@@ -0,0 +1,357 @@
+use crate::{
+    data_handler::DataResponse,
+    lister::{CustomList, ListEntry, PathType},
+    theme::{self, Slot},
+    ui::{centered_rect, UiFrame},
+};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::cmp::Reverse;
+use tui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+const FINDER: &str = "find : [go to: Enter], [open: Tab], [move: Up/Down], [close: Esc]";
+const PAGE_JUMP: usize = 10;
+
+pub(crate) enum FinderOutcome {
+    Searching,
+    Close,
+    /// the path, relative to the finder's url, to select in its directory
+    GoTo(String),
+    /// the same, and then opened like the list opens it
+    Open(String),
+}
+
+/// fuzzy search over every path below a directory, ranked while typing
+pub(crate) struct Finder {
+    url: String,
+    /// named by their path from `url`
+    entries: Vec<ListEntry>,
+    /// svn is still listing, or about to
+    listing: bool,
+    query: String,
+    /// indices of the entries matching the query, best first
+    matches: Vec<usize>,
+    /// position in `matches`
+    selected: usize,
+    /// first match shown, as of the last draw
+    top: usize,
+}
+
+impl Finder {
+    pub(crate) fn new(url: String) -> Self {
+        Self {
+            url,
+            entries: vec![],
+            listing: false,
+            query: String::new(),
+            matches: vec![],
+            selected: 0,
+            top: 0,
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub(crate) fn listing(&self) -> bool {
+        self.listing
+    }
+
+    pub(crate) fn start_listing(&mut self) {
+        self.listing = true;
+    }
+
+    /// the listing failed or was cancelled; what came in so far can still be searched
+    pub(crate) fn stop_listing(&mut self) {
+        self.listing = false;
+    }
+
+    /// the parts of a tree while it is listed, then the whole of it
+    pub(crate) fn receive(&mut self, response: DataResponse) {
+        match response {
+            DataResponse::TreePart(at, part) => {
+                self.listing = true;
+                self.entries.truncate(at);
+                self.entries.extend(part);
+            }
+            DataResponse::Tree(entries) => {
+                self.listing = false;
+                self.entries = entries;
+            }
+            _ => return,
+        }
+        self.rank();
+    }
+
+    pub(crate) fn handle(&mut self, code: KeyCode, modifiers: KeyModifiers) -> FinderOutcome {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        match code {
+            KeyCode::Esc => return FinderOutcome::Close,
+            KeyCode::Enter | KeyCode::Tab => {
+                let Some(entry) = self.matches.get(self.selected).map(|i| &self.entries[*i]) else {
+                    return FinderOutcome::Searching;
+                };
+                let path = entry.name.clone();
+                return if code == KeyCode::Enter {
+                    FinderOutcome::GoTo(path)
+                } else {
+                    FinderOutcome::Open(path)
+                };
+            }
+            KeyCode::Up => self.up(1),
+            KeyCode::Char('p') if ctrl => self.up(1),
+            KeyCode::Down => self.down(1),
+            KeyCode::Char('n') if ctrl => self.down(1),
+            KeyCode::PageUp => self.up(PAGE_JUMP),
+            KeyCode::PageDown => self.down(PAGE_JUMP),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+                self.rank();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.selected = 0;
+                self.rank();
+            }
+            _ => {}
+        }
+        FinderOutcome::Searching
+    }
+
+    fn up(&mut self, n: usize) {
+        self.selected = self.selected.saturating_sub(n);
+    }
+
+    fn down(&mut self, n: usize) {
+        self.selected = (self.selected + n).min(self.matches.len().saturating_sub(1));
+    }
+
+    /// every entry while the query is empty; ties keep the order of the listing
+    fn rank(&mut self) {
+        let query = lowercase(&self.query);
+        if query.is_empty() {
+            self.matches = (0..self.entries.len()).collect();
+        } else {
+            let mut scored: Vec<(i32, usize)> = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter_map(|(i, e)| fuzzy(&query, &e.name).map(|(score, _)| (score, i)))
+                .collect();
+            scored.sort_by_key(|(score, i)| (Reverse(*score), self.entries[*i].name.len()));
+            self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        }
+        self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+    }
+
+    /// the lists from the finder's directory down to the one holding `path`, built from the tree
+    /// rather than asking svn again, and the index of `path` in the last of them
+    pub(crate) fn lists_to(&self, path: &str) -> (Vec<CustomList>, usize) {
+        let (parent, name) = match path.rsplit_once('/') {
+            Some((parent, name)) => (Some(parent), name),
+            None => (None, path),
+        };
+        let mut dirs = vec![String::new()];
+        if let Some(parent) = parent {
+            let mut dir = String::new();
+            for segment in parent.split('/') {
+                dir.push_str(segment);
+                dir.push('/');
+                dirs.push(dir.clone());
+            }
+        }
+        let lists: Vec<CustomList> = dirs
+            .iter()
+            .map(|dir| {
+                let entries = self
+                    .entries
+                    .iter()
+                    .filter_map(|e| {
+                        let rest = e.name.strip_prefix(dir.as_str())?;
+                        (!rest.contains('/')).then(|| ListEntry {
+                            kind: e.kind,
+                            name: rest.to_owned(),
+                            commit: e.commit.clone(),
+                        })
+                    })
+                    .collect();
+                CustomList::from((entries, format!("{}{dir}", self.url)))
+            })
+            .collect();
+        let index = lists
+            .last()
+            .and_then(|l| l.entries().iter().position(|e| e.name == name))
+            .unwrap_or(0);
+        (lists, index)
+    }
+
+    pub(crate) fn render(&mut self, frame: &mut UiFrame) {
+        let area = centered_rect(80, 80, frame.size());
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.query)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(FINDER)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            ),
+            chunks[0],
+        );
+
+        // only the rows on screen are built, the tree may have many thousand entries
+        let height = usize::from(chunks[1].height.saturating_sub(2)).max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + height {
+            self.top = self.selected + 1 - height;
+        }
+        let query = lowercase(&self.query);
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .skip(self.top)
+            .take(height)
+            .map(|i| {
+                let entry = &self.entries[*i];
+                let positions = fuzzy(&query, &entry.name).map_or_else(Vec::new, |(_, p)| p);
+                let style = match entry.kind {
+                    PathType::Dir => theme::fg(Slot::Directory),
+                    PathType::File => theme::fg(Slot::File),
+                };
+                let mut spans: Vec<Span> = entry
+                    .name
+                    .chars()
+                    .enumerate()
+                    .map(|(n, c)| {
+                        if positions.contains(&n) {
+                            Span::styled(
+                                c.to_string(),
+                                theme::fg(Slot::Accent).add_modifier(Modifier::BOLD),
+                            )
+                        } else {
+                            Span::styled(c.to_string(), style)
+                        }
+                    })
+                    .collect();
+                if entry.kind == PathType::Dir {
+                    spans.push(Span::styled("/", style));
+                }
+                ListItem::new(Spans::from(spans))
+            })
+            .collect();
+        let title = format!(
+            "{} : {}/{}{}",
+            self.url,
+            self.matches.len(),
+            self.entries.len(),
+            if self.listing { " (listing...)" } else { "" }
+        );
+        let mut state = ListState::default();
+        state.select((!self.matches.is_empty()).then(|| self.selected - self.top));
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            )
+            .highlight_style(Style::default().bg(theme::color(Slot::SelectedBg)))
+            .highlight_symbol(">>");
+        frame.render_stateful_widget(list, chunks[1], &mut state);
+    }
+}
+
+fn lowercase(s: &str) -> Vec<char> {
+    s.chars().map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// how well `path` matches `query`, which has to appear in it in order but not in one piece,
+/// ignoring case; with the char positions of the match, for highlighting
+///
+/// like fzf's first algorithm: the first place the query fits in, narrowed from its end back to
+/// the latest start. characters starting a word, following each other or in the file name
+/// score higher, gaps lower.
+fn fuzzy(query: &[char], path: &str) -> Option<(i32, Vec<usize>)> {
+    if query.is_empty() {
+        return Some((0, vec![]));
+    }
+    let chars = lowercase(path);
+    let mut found = 0;
+    let end = chars.iter().position(|c| {
+        if *c == query[found] {
+            found += 1;
+        }
+        found == query.len()
+    })?;
+    let mut positions = Vec::with_capacity(query.len());
+    for i in (0..=end).rev() {
+        if chars[i] == query[query.len() - 1 - positions.len()] {
+            positions.push(i);
+            if positions.len() == query.len() {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+    let name_start = chars.iter().rposition(|c| *c == '/').map_or(0, |i| i + 1);
+    let mut score = 0;
+    for (n, &i) in positions.iter().enumerate() {
+        score += 16;
+        if i == 0 || matches!(chars[i - 1], '/' | '_' | '-' | '.' | ' ') {
+            score += 8;
+        }
+        if n > 0 && positions[n - 1] + 1 == i {
+            score += 4;
+        }
+        if i >= name_start {
+            score += 4;
+        }
+    }
+    let span = positions[positions.len() - 1] - positions[0] + 1;
+    score -= (span - positions.len()) as i32;
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, path: &str) -> Option<i32> {
+        fuzzy(&lowercase(query), path).map(|(score, _)| score)
+    }
+
+    #[test]
+    fn fuzzy_matches_in_order_ignoring_case() {
+        let (_, positions) = fuzzy(&lowercase("mR"), "src/Main.rs").unwrap();
+        assert_eq!(positions, [4, 9]);
+        assert!(score("rm", "src/main.rs").is_some());
+        assert!(score("sm", "src/x.rs").is_none());
+        assert!(score("mainx", "src/main.rs").is_none());
+        assert_eq!(fuzzy(&[], "anything"), Some((0, vec![])));
+    }
+
+    #[test]
+    fn fuzzy_narrows_to_the_latest_start() {
+        let (_, positions) = fuzzy(&lowercase("ab"), "a_xx_ab").unwrap();
+        assert_eq!(positions, [5, 6]);
+    }
+
+    #[test]
+    fn fuzzy_prefers_word_starts_runs_and_file_names() {
+        assert!(score("lv", "list_view.rs") > score("lv", "solver.rs"));
+        assert!(score("main", "main.rs") > score("main", "mxaxixn.rs"));
+        assert!(score("util", "lib/util.rs") > score("util", "util/lib.rs"));
+    }
+}
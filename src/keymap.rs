@@ -0,0 +1,447 @@
+use crate::CustomError;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, sync::OnceLock};
+
+/// printed by `--dump-default-keymap`; also the bindings used for actions the config leaves out
+pub(crate) const DEFAULT_KEYMAP: &str = r#"# copy into the config file and edit; an action listed there loses all of its default keys.
+#
+# a key is a character or one of esc, enter, tab, backtab, space, backspace, delete, insert,
+# up, down, left, right, home, end, pageup, pagedown and f1-f12, optionally prefixed with
+# ctrl-, alt- or shift-. "g g" or "ctrl-w j" are sequences, pressed one key after the other.
+# text fields, like the login prompt and the commit message, keep their own keys.
+[keymap]
+quit = ["q"]
+# stops running requests; asks to quit if nothing is running
+cancel = ["esc"]
+up = ["k", "up"]
+down = ["j", "down"]
+page_up = ["pageup"]
+page_down = ["pagedown"]
+top = ["g g", "home"]
+bottom = ["G", "end"]
+open = ["l", "right", "enter"]
+back = ["h", "left"]
+focus_next = ["tab"]
+focus_prev = ["backtab"]
+focus_list = ["1"]
+focus_text = ["2"]
+focus_commit_message = ["3"]
+# shows the focused pane alone, until pressed again
+zoom = ["z"]
+refresh = ["r"]
+reload_file = ["f"]
+blame = ["B"]
+# in blame: the log and diff of the line's revision, and the blame before it
+show_revision = ["o"]
+reblame = ["b"]
+diff = ["D"]
+log = ["L"]
+# fuzzy search over every path below the current directory
+find = ["t"]
+status = ["S"]
+cache = ["C"]
+errors = ["E"]
+activity = ["A"]
+retry = ["R"]
+# in the list
+search = ["/"]
+next_match = ["n"]
+prev_match = ["N"]
+filter = ["F"]
+scroll_text_up = ["u"]
+scroll_text_down = ["d"]
+scroll_message_up = ["9"]
+scroll_message_down = ["0"]
+next_hunk = ["]"]
+prev_hunk = ["["]
+next_file = ["}"]
+prev_file = ["{"]
+toggle_diff_mode = ["v"]
+# in the log browser and the status view
+mark = ["m", "space"]
+commit = ["c"]
+toggle_incoming = ["U"]
+# in the cache popup
+clear_subtree = ["s"]
+clear_all = ["a"]
+"#;
+
+/// what a key does; which view acts on it depends on what is open and focused
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Action {
+    Quit,
+    Cancel,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Open,
+    Back,
+    FocusNext,
+    FocusPrev,
+    FocusList,
+    FocusText,
+    FocusCommitMessage,
+    Zoom,
+    Refresh,
+    ReloadFile,
+    Blame,
+    ShowRevision,
+    Reblame,
+    Diff,
+    Log,
+    Find,
+    Status,
+    Cache,
+    Errors,
+    Activity,
+    Retry,
+    Search,
+    NextMatch,
+    PrevMatch,
+    Filter,
+    ScrollTextUp,
+    ScrollTextDown,
+    ScrollMessageUp,
+    ScrollMessageDown,
+    NextHunk,
+    PrevHunk,
+    NextFile,
+    PrevFile,
+    ToggleDiffMode,
+    Mark,
+    Commit,
+    ToggleIncoming,
+    ClearSubtree,
+    ClearAll,
+}
+
+static HINTS: OnceLock<Vec<(Vec<Key>, Action)>> = OnceLock::new();
+
+#[derive(Deserialize)]
+struct KeymapFile {
+    keymap: HashMap<Action, Vec<String>>,
+}
+
+/// a key press with its modifiers; shift is part of the character itself
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+        if matches!(event.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    /// the way hints show it, like 'q', Esc or Ctrl-w
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl-"),
+            (KeyModifiers::ALT, "Alt-"),
+            (KeyModifiers::SHIFT, "Shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "'{c}'"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::BackTab => write!(f, "Shift-Tab"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    let code = match name {
+        "esc" => KeyCode::Esc,
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => match name.strip_prefix('f').map(str::parse) {
+            Some(Ok(n @ 1..=12)) => KeyCode::F(n),
+            _ => return None,
+        },
+    };
+    Some(code)
+}
+
+/// one word of a binding: a key with its modifiers. keys of a sequence are separate words, so a
+/// misspelled key name is an error rather than a sequence of its letters
+fn parse_word(word: &str) -> Result<Key, CustomError> {
+    let invalid = || CustomError::InvalidKey(word.to_owned());
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = word;
+    loop {
+        let (modifier, stripped) = if let Some(s) = rest.strip_prefix("ctrl-") {
+            (KeyModifiers::CONTROL, s)
+        } else if let Some(s) = rest.strip_prefix("alt-") {
+            (KeyModifiers::ALT, s)
+        } else if let Some(s) = rest.strip_prefix("shift-") {
+            (KeyModifiers::SHIFT, s)
+        } else {
+            break;
+        };
+        // "ctrl--" is ctrl and the minus key
+        if stripped.is_empty() {
+            break;
+        }
+        modifiers |= modifier;
+        rest = stripped;
+    }
+    let mut code = match named_key(rest) {
+        Some(code) => code,
+        None => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return Err(invalid()),
+            }
+        }
+    };
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_uppercase()),
+            KeyCode::Tab => KeyCode::BackTab,
+            code => code,
+        };
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+    }
+    Ok(Key { code, modifiers })
+}
+
+fn parse_binding(binding: &str) -> Result<Vec<Key>, CustomError> {
+    let keys = binding
+        .split_whitespace()
+        .map(parse_word)
+        .collect::<Result<Vec<Key>, CustomError>>()?;
+    if keys.is_empty() {
+        return Err(CustomError::InvalidKey(binding.to_owned()));
+    }
+    Ok(keys)
+}
+
+/// turns key presses into actions, waiting for the rest of a sequence when needed
+pub(crate) struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+    /// keys of a sequence typed so far
+    pending: Vec<Key>,
+}
+
+impl Keymap {
+    /// the default bindings, with the actions in `overrides` bound to their keys instead
+    pub(crate) fn new(overrides: &HashMap<Action, Vec<String>>) -> Result<Self, CustomError> {
+        let KeymapFile { keymap: defaults } = toml::from_str(DEFAULT_KEYMAP)?;
+        let mut bindings = vec![];
+        for (action, keys) in defaults.iter().filter(|(a, _)| !overrides.contains_key(a)) {
+            for binding in keys {
+                bindings.push((parse_binding(binding)?, *action));
+            }
+        }
+        let mut custom = vec![];
+        for (action, keys) in overrides {
+            for binding in keys {
+                custom.push((parse_binding(binding)?, *action));
+            }
+        }
+        // a key taken by the config no longer does what it did by default
+        bindings.retain(|(keys, _)| !custom.iter().any(|(k, _)| k == keys));
+        bindings.extend(custom);
+        Ok(Self {
+            bindings,
+            pending: vec![],
+        })
+    }
+
+    /// the action of this key, or of the sequence it completes; None while a sequence is
+    /// still being typed and for keys bound to nothing.
+    /// a binding that is also the start of a longer one always wins.
+    pub(crate) fn resolve(&mut self, key: KeyEvent) -> Option<Action> {
+        self.pending.push(key.into());
+        loop {
+            if let Some((_, action)) = self.bindings.iter().find(|(keys, _)| *keys == self.pending)
+            {
+                self.pending.clear();
+                return Some(*action);
+            }
+            if self
+                .bindings
+                .iter()
+                .any(|(keys, _)| keys.starts_with(&self.pending))
+            {
+                return None;
+            }
+            // not a sequence after all; the last key may still mean something on its own
+            if self.pending.len() == 1 {
+                self.pending.clear();
+                return None;
+            }
+            self.pending.drain(..self.pending.len() - 1);
+        }
+    }
+}
+
+/// makes the bindings of `keymap` the ones hints name; only the first call counts
+pub(crate) fn set_hints(keymap: &Keymap) {
+    let _ = HINTS.set(keymap.bindings.clone());
+}
+
+// hints are part of titles and messages all over, so like the theme the bindings they name are
+// global rather than handed to every render call
+fn hints() -> &'static [(Vec<Key>, Action)] {
+    HINTS.get_or_init(|| {
+        Keymap::new(&HashMap::new())
+            .map(|keymap| keymap.bindings)
+            .unwrap_or_default()
+    })
+}
+
+fn show(keys: &[Key]) -> String {
+    keys.iter()
+        .map(Key::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the first key bound to `action`, for hints
+pub(crate) fn key(action: Action) -> String {
+    match hints().iter().find(|(_, a)| *a == action) {
+        Some((keys, _)) => show(keys),
+        None => "unbound".to_owned(),
+    }
+}
+
+/// the keys of several actions that share a hint, like 'j'/'k'
+pub(crate) fn keys(actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(|a| key(*a))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        Key { code, modifiers }
+    }
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers() {
+        assert_eq!(
+            parse_binding("ctrl-w").unwrap(),
+            vec![key(KeyCode::Char('w'), KeyModifiers::CONTROL)]
+        );
+        assert_eq!(
+            parse_binding("ctrl--").unwrap(),
+            vec![key(KeyCode::Char('-'), KeyModifiers::CONTROL)]
+        );
+        assert_eq!(
+            parse_binding("alt-pagedown").unwrap(),
+            vec![key(KeyCode::PageDown, KeyModifiers::ALT)]
+        );
+        assert_eq!(
+            parse_binding("shift-g").unwrap(),
+            vec![key(KeyCode::Char('G'), KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            parse_binding("shift-tab").unwrap(),
+            vec![key(KeyCode::BackTab, KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            parse_binding("f12").unwrap(),
+            vec![key(KeyCode::F(12), KeyModifiers::NONE)]
+        );
+    }
+
+    #[test]
+    fn parses_sequences_word_by_word() {
+        assert_eq!(
+            parse_binding("ctrl-w j").unwrap(),
+            vec![
+                key(KeyCode::Char('w'), KeyModifiers::CONTROL),
+                key(KeyCode::Char('j'), KeyModifiers::NONE)
+            ]
+        );
+        assert_eq!(parse_binding("g g").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        for binding in [
+            "escape", "pageUp", "f13", "gg", "ctrl-", "ctrl-foo", "", "  ",
+        ] {
+            assert!(
+                matches!(parse_binding(binding), Err(CustomError::InvalidKey(_))),
+                "{binding:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_sequences_and_overrides() {
+        let mut keymap = Keymap::new(&HashMap::new()).unwrap();
+        assert_eq!(keymap.resolve(press('g')), None);
+        assert_eq!(keymap.resolve(press('g')), Some(Action::Top));
+        assert_eq!(keymap.resolve(press('G')), Some(Action::Bottom));
+        // a sequence broken off still lets its last key count
+        assert_eq!(keymap.resolve(press('g')), None);
+        assert_eq!(keymap.resolve(press('j')), Some(Action::Down));
+
+        let overrides = HashMap::from([(Action::Quit, vec!["j".to_owned()])]);
+        let mut keymap = Keymap::new(&overrides).unwrap();
+        assert_eq!(keymap.resolve(press('j')), Some(Action::Quit));
+        assert_eq!(keymap.resolve(press('q')), None);
+    }
+    #[test]
+    fn shows_keys_like_hints() {
+        let shown = |binding| show(&parse_binding(binding).unwrap());
+        assert_eq!(shown("q"), "'q'");
+        assert_eq!(shown("shift-g"), "'G'");
+        assert_eq!(shown("space"), "space");
+        assert_eq!(shown("esc"), "Esc");
+        assert_eq!(shown("backtab"), "Shift-Tab");
+        assert_eq!(shown("ctrl-w j"), "Ctrl-w 'j'");
+        assert_eq!(shown("alt-pagedown"), "Alt-PgDn");
+    }
+}
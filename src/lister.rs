@@ -1,17 +1,116 @@
+use crate::{
+    config::Login,
+    search::ListFilter,
+    theme::{self, Slot},
+};
+use serde::{Deserialize, Serialize};
 use std::{
-    io,
-    sync::{Arc, Mutex},
-    time::{Duration, SystemTimeError},
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTimeError},
 };
-use svn_cmd::{Credentials, ListEntry, SvnCmd, SvnError, SvnInfo, SvnList};
 use tui::widgets::{ListItem, ListState};
 
-pub(crate) const MAX_VALIDITY_OF_CACHED_LIST: Duration = Duration::from_secs(15 * 60);
+/// how often a running svn process is checked for cancellation and its timeout
+const CANCEL_POLL: Duration = Duration::from_millis(20);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum PathType {
+    File,
+    Dir,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct CommitAttribs {
+    pub(crate) revision: u32,
+    pub(crate) author: String,
+    pub(crate) date: String,
+}
+
+/// one entry of `svn list`, in a form that can be stored in the on-disk cache
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ListEntry {
+    pub(crate) kind: PathType,
+    pub(crate) name: String,
+    pub(crate) commit: CommitAttribs,
+}
+
+/// `svn list --xml`, the parts of it we keep
+#[derive(Deserialize)]
+struct XmlLists {
+    list: XmlList,
+}
+
+#[derive(Deserialize)]
+struct XmlList {
+    #[serde(default)]
+    entry: Vec<XmlEntry>,
+}
+
+#[derive(Deserialize)]
+struct XmlEntry {
+    #[serde(rename = "@kind")]
+    kind: String,
+    name: String,
+    commit: XmlCommit,
+}
+
+#[derive(Deserialize)]
+struct XmlCommit {
+    #[serde(rename = "@revision")]
+    revision: u32,
+    /// missing for anonymous commits
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    date: String,
+}
+
+/// one entry of `svn info`, the parts of it we keep
+#[derive(Clone, Debug)]
+pub(crate) struct InfoEntry {
+    pub(crate) url: String,
+}
+
+/// `svn info --xml`
+#[derive(Deserialize)]
+struct XmlInfo {
+    entry: XmlInfoEntry,
+}
+
+#[derive(Deserialize)]
+struct XmlInfoEntry {
+    url: String,
+}
+
+impl From<XmlEntry> for ListEntry {
+    fn from(e: XmlEntry) -> Self {
+        Self {
+            kind: if e.kind == "dir" {
+                PathType::Dir
+            } else {
+                PathType::File
+            },
+            name: e.name,
+            commit: CommitAttribs {
+                revision: e.commit.revision,
+                author: e.commit.author,
+                date: e.commit.date,
+            },
+        }
+    }
+}
 
 pub(crate) trait ListOps {
     fn len(&self) -> usize;
     fn get_list_items(&self) -> Vec<ListItem>;
-    fn get_current_selected(&self, state: Arc<Mutex<CustomListState>>) -> Option<ListEntry>;
+    fn get_current_selected(&self, state: &CustomListState) -> Option<ListEntry>;
 }
 
 pub(crate) trait ListStateOps {
@@ -23,26 +122,207 @@ pub(crate) trait ListStateOps {
 pub(crate) mod svn_helper {
     use super::*;
 
-    pub(crate) fn new() -> SvnCmd {
-        let cmd = SvnCmd::new(
-            Some(Credentials {
-                username: "svc-p-blsrobo".to_owned(),
-                password: "Comewel@12345".to_owned(),
-            }),
-            None,
-        );
-        cmd
+    /// set from another thread to abandon a running svn command; its process is killed
+    #[derive(Clone, Default, Debug)]
+    pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+    impl CancelToken {
+        pub(crate) fn cancel(&self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+
+        pub(crate) fn is_cancelled(&self) -> bool {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    /// the password is written to svn's stdin, anything on its command line can be read by
+    /// every local user
+    fn spawn(login: Option<&Login>, args: &[&str]) -> Result<Child, CustomError> {
+        let mut cmd = Command::new("svn");
+        cmd.args(args).arg("--non-interactive");
+        if let Some(l) = login {
+            cmd.args(["--username", &l.username, "--password-from-stdin"]);
+        }
+        let mut child = cmd
+            .stdin(if login.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let (Some(l), Some(mut stdin)) = (login, child.stdin.take()) {
+            // svn reads the first line only; stdin is closed when dropped
+            let _ = writeln!(stdin, "{}", l.password);
+        }
+        Ok(child)
+    }
+
+    /// read on its own thread, so a full pipe never blocks svn
+    fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut buf = vec![];
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+
+    /// waits for svn to exit, calling `poll` in between; kills it as soon as `cancel` is set or
+    /// it runs longer than `timeout`
+    fn wait(
+        child: &mut Child,
+        cancel: &CancelToken,
+        timeout: Option<Duration>,
+        poll: &mut dyn FnMut(),
+    ) -> Result<ExitStatus, CustomError> {
+        let started = Instant::now();
+        loop {
+            let timed_out = timeout.filter(|t| started.elapsed() >= *t);
+            if cancel.is_cancelled() || timed_out.is_some() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(timed_out.map_or(CustomError::Cancelled, CustomError::TimedOut));
+            }
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            poll();
+            thread::sleep(CANCEL_POLL);
+        }
+    }
+
+    fn failed(stderr: thread::JoinHandle<Vec<u8>>) -> CustomError {
+        let stderr = stderr.join().unwrap_or_default();
+        CustomError::SvnCli(String::from_utf8_lossy(&stderr).trim().to_owned())
+    }
+
+    /// runs the svn binary and returns what it printed; kills it as soon as `cancel` is set or it
+    /// runs longer than `timeout`
+    pub(crate) fn run_cancellable(
+        login: Option<&Login>,
+        args: &[&str],
+        cancel: &CancelToken,
+        timeout: Option<Duration>,
+    ) -> Result<String, CustomError> {
+        let mut child = spawn(login, args)?;
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+        let status = wait(&mut child, cancel, timeout, &mut || {})?;
+        if !status.success() {
+            return Err(failed(stderr));
+        }
+        let stdout = stdout.join().unwrap_or_default();
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+
+    /// like `run_cancellable`, but hands every line of the output to `on_line` while svn is still
+    /// running, instead of all of it at the end
+    pub(crate) fn run_streamed(
+        login: Option<&Login>,
+        args: &[&str],
+        cancel: &CancelToken,
+        timeout: Option<Duration>,
+        on_line: &mut dyn FnMut(String),
+    ) -> Result<(), CustomError> {
+        let mut child = spawn(login, args)?;
+        let (tx, rx) = mpsc::channel();
+        let stdout = child.stdout.take().map(|pipe| {
+            thread::spawn(move || {
+                for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+        });
+        let stderr = drain(child.stderr.take());
+        let status = wait(&mut child, cancel, timeout, &mut || {
+            rx.try_iter().for_each(&mut *on_line)
+        })?;
+        // what svn printed just before it exited
+        if let Some(stdout) = stdout {
+            let _ = stdout.join();
+        }
+        rx.try_iter().for_each(&mut *on_line);
+        if !status.success() {
+            return Err(failed(stderr));
+        }
+        Ok(())
     }
 
-    pub(crate) fn info(cmd: &SvnCmd) -> Result<SvnInfo, CustomError> {
-        let info = cmd.info(".")?;
-        Ok(info)
+    /// `svn info` of one target, run like `run_cancellable`
+    pub(crate) fn info(
+        login: Option<&Login>,
+        target: &str,
+        cancel: &CancelToken,
+        timeout: Option<Duration>,
+    ) -> Result<InfoEntry, CustomError> {
+        let xml = run_cancellable(login, &["info", "--xml", target], cancel, timeout)?;
+        let info: XmlInfo = quick_xml::de::from_str(&xml)?;
+        Ok(InfoEntry {
+            url: info.entry.url,
+        })
+    }
+
+    pub(crate) fn list(
+        login: Option<&Login>,
+        url: &str,
+        cancel: &CancelToken,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<ListEntry>, CustomError> {
+        let xml = run_cancellable(login, &["list", "--xml", url], cancel, timeout)?;
+        let lists: XmlLists = quick_xml::de::from_str(&xml)?;
+        Ok(lists.list.entry.into_iter().map(ListEntry::from).collect())
+    }
+
+    /// `svn list -R`, every entry handed to `on_entry` as soon as svn printed it; the names are
+    /// paths below `url`
+    pub(crate) fn list_recursive(
+        login: Option<&Login>,
+        url: &str,
+        cancel: &CancelToken,
+        timeout: Option<Duration>,
+        on_entry: &mut dyn FnMut(ListEntry),
+    ) -> Result<(), CustomError> {
+        // svn puts the tags of an entry on lines of their own, so it is complete at `</entry>`
+        let mut entry = String::new();
+        let mut error = None;
+        run_streamed(
+            login,
+            &["list", "-R", "--xml", url],
+            cancel,
+            timeout,
+            &mut |line| {
+                if line.starts_with("<entry") {
+                    entry.clear();
+                }
+                entry.push_str(&line);
+                entry.push('\n');
+                if line.starts_with("</entry>") {
+                    match quick_xml::de::from_str::<XmlEntry>(&entry) {
+                        Ok(e) => on_entry(e.into()),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                        }
+                    }
+                    entry.clear();
+                }
+            },
+        )?;
+        error.map_or(Ok(()), |e| Err(e.into()))
     }
 }
 
 #[derive(Default, Clone)]
 pub(crate) struct CustomList {
-    items: SvnList,
+    /// the entries the filter lets through
+    items: Vec<ListEntry>,
+    all: Vec<ListEntry>,
+    filter: Option<ListFilter>,
     pub(crate) base_url: String,
 }
 
@@ -52,6 +332,15 @@ pub(crate) struct CustomListState {
     list_size: usize,
 }
 
+impl CustomListState {
+    /// keeps the selection of a list that was refreshed in place, as far as it still fits
+    pub(crate) fn select(&mut self, selected: Option<usize>) {
+        if let Some(selected) = selected.filter(|_| self.list_size > 0) {
+            self.state.select(Some(selected.min(self.list_size - 1)));
+        }
+    }
+}
+
 impl ListStateOps for CustomListState {
     fn get(&self) -> Option<usize> {
         self.state.selected()
@@ -78,22 +367,52 @@ impl ListStateOps for CustomListState {
     }
 }
 
+impl CustomList {
+    /// the entries shown, without those the filter hides
+    pub(crate) fn entries(&self) -> &[ListEntry] {
+        &self.items
+    }
+
+    pub(crate) fn filter(&self) -> Option<&ListFilter> {
+        self.filter.as_ref()
+    }
+
+    /// None shows every entry again
+    pub(crate) fn set_filter(&mut self, filter: Option<ListFilter>) {
+        self.items = match &filter {
+            Some(f) => self
+                .all
+                .iter()
+                .filter(|e| f.matches(&e.name))
+                .cloned()
+                .collect(),
+            None => self.all.clone(),
+        };
+        self.filter = filter;
+    }
+}
+
 impl ListOps for CustomList {
     fn len(&self) -> usize {
-        self.items.iter().unwrap().count()
+        self.items.len()
     }
 
     fn get_list_items(&self) -> Vec<ListItem> {
         self.items
             .iter()
-            .unwrap()
-            .map(|i| ListItem::new(i.name.as_ref()))
+            .map(|i| {
+                let slot = match i.kind {
+                    PathType::Dir => Slot::Directory,
+                    PathType::File => Slot::File,
+                };
+                ListItem::new(i.name.as_ref()).style(theme::fg(slot))
+            })
             .collect()
     }
 
-    fn get_current_selected(&self, state: Arc<Mutex<CustomListState>>) -> Option<ListEntry> {
-        if let Some(selected) = state.lock().unwrap().get() {
-            if let Some(item) = self.items.iter().unwrap().nth(selected) {
+    fn get_current_selected(&self, state: &CustomListState) -> Option<ListEntry> {
+        if let Some(selected) = state.get() {
+            if let Some(item) = self.items.get(selected) {
                 return Some(item.clone());
             }
         }
@@ -115,16 +434,18 @@ impl From<CustomList> for CustomListState {
 impl From<String> for CustomList {
     fn from(base_url: String) -> Self {
         Self {
-            items: SvnList::default(),
             base_url,
+            ..Self::default()
         }
     }
 }
 
-impl From<(SvnList, String)> for CustomList {
-    fn from(pair: (SvnList, String)) -> Self {
+impl From<(Vec<ListEntry>, String)> for CustomList {
+    fn from(pair: (Vec<ListEntry>, String)) -> Self {
         Self {
-            items: pair.0,
+            items: pair.0.clone(),
+            all: pair.0,
+            filter: None,
             base_url: pair.1,
         }
     }
@@ -133,20 +454,88 @@ impl From<(SvnList, String)> for CustomList {
 #[derive(Debug)]
 pub(crate) enum CustomError {
     Io(io::Error),
-    Svn(SvnError),
+    SvnCli(String),
     SystemTime(SystemTimeError),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    Xml(quick_xml::DeError),
+    /// a search or filter pattern that can't be compiled
+    Regex(regex::Error),
+    /// the request was superseded or cancelled by the user
+    Cancelled,
+    /// svn was killed after running this long
+    TimedOut(Duration),
+    InvalidTarget(String),
+    /// a key of the keymap that can't be parsed
+    InvalidKey(String),
+    /// a colour of the theme that can't be parsed
+    InvalidColor(String),
     NoDataToList,
 }
 
-impl From<io::Error> for CustomError {
-    fn from(e: io::Error) -> Self {
-        CustomError::Io(e)
+impl fmt::Display for CustomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomError::Io(e) => write!(f, "io error: {e}"),
+            CustomError::SvnCli(stderr) => write!(f, "svn error: {stderr}"),
+            CustomError::SystemTime(e) => write!(f, "system time error: {e}"),
+            CustomError::Toml(e) => write!(f, "invalid config file: {e}"),
+            CustomError::Json(e) => write!(f, "invalid cache entry: {e}"),
+            CustomError::Xml(e) => write!(f, "unexpected svn output: {e}"),
+            CustomError::Regex(e) => {
+                // the last line of a syntax error says what is wrong, the others show where
+                let e = e.to_string();
+                let reason = e.lines().last().unwrap_or_default();
+                write!(
+                    f,
+                    "invalid pattern: {}",
+                    reason.trim_start_matches("error: ")
+                )
+            }
+            CustomError::Cancelled => write!(f, "cancelled"),
+            CustomError::TimedOut(t) => write!(f, "svn didn't finish within {}s", t.as_secs()),
+            CustomError::InvalidTarget(msg) => write!(f, "{msg}"),
+            CustomError::InvalidKey(key) => write!(f, "invalid key in the keymap: '{key}'"),
+            CustomError::InvalidColor(color) => write!(f, "invalid colour in the theme: '{color}'"),
+            CustomError::NoDataToList => write!(f, "no data to list"),
+        }
     }
 }
 
-impl From<SvnError> for CustomError {
-    fn from(e: SvnError) -> Self {
-        CustomError::Svn(e)
+impl CustomError {
+    fn svn_output(&self) -> Option<String> {
+        match self {
+            CustomError::SvnCli(stderr) => Some(stderr.clone()),
+            _ => None,
+        }
+    }
+
+    /// svn reports rejected or missing credentials with these error codes
+    pub(crate) fn is_auth(&self) -> bool {
+        // E170013 "unable to connect" isn't among them: it wraps network errors just as well
+        const AUTH_ERRORS: [&str; 2] = ["E170001", "E215004"];
+        self.svn_output()
+            .is_some_and(|e| AUTH_ERRORS.iter().any(|code| e.contains(code)))
+    }
+
+    /// network trouble that may well be gone on the next attempt
+    pub(crate) fn is_transient(&self) -> bool {
+        // connection reset, timed out, refused, no route to host, http request failed,
+        // http timeout, svn:// connection closed. not our own timeout: a request that ran into
+        // it would only run into it again, and not a host name that doesn't resolve either
+        const TRANSIENT_ERRORS: [&str; 8] = [
+            "E000104", "E000110", "E000111", "E000113", "E670008", "E175002", "E175012", "E210002",
+        ];
+        !self.is_auth()
+            && self
+                .svn_output()
+                .is_some_and(|e| TRANSIENT_ERRORS.iter().any(|code| e.contains(code)))
+    }
+}
+
+impl From<io::Error> for CustomError {
+    fn from(e: io::Error) -> Self {
+        CustomError::Io(e)
     }
 }
 
@@ -156,6 +545,30 @@ impl From<SystemTimeError> for CustomError {
     }
 }
 
+impl From<toml::de::Error> for CustomError {
+    fn from(e: toml::de::Error) -> Self {
+        CustomError::Toml(e)
+    }
+}
+
+impl From<regex::Error> for CustomError {
+    fn from(e: regex::Error) -> Self {
+        CustomError::Regex(e)
+    }
+}
+
+impl From<serde_json::Error> for CustomError {
+    fn from(e: serde_json::Error) -> Self {
+        CustomError::Json(e)
+    }
+}
+
+impl From<quick_xml::DeError> for CustomError {
+    fn from(e: quick_xml::DeError) -> Self {
+        CustomError::Xml(e)
+    }
+}
+
 pub(crate) struct CustomLists {
     lists: Vec<CustomList>,
     current: usize,
@@ -180,6 +593,14 @@ impl CustomLists {
         self.current += 1;
     }
 
+    /// swaps the current list for a fresher copy of itself, keeping the history
+    pub(crate) fn replace_current(&mut self, list: CustomList) {
+        match self.lists.get_mut(self.current) {
+            Some(cur) => *cur = list,
+            None => self.add_new_list(list),
+        }
+    }
+
     pub(crate) fn go_back(&mut self) -> CustomListsToDisplay {
         if self.current > 0 {
             self.current -= 1;
@@ -0,0 +1,217 @@
+use crate::{
+    data_handler::{DataRequest, TargetUrl},
+    diff::DiffRange,
+    keymap::Action,
+    revlog::LogEntry,
+    theme::{self, Slot},
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+/// revisions fetched per `svn log -v` call
+pub(crate) const LOG_PAGE_SIZE: usize = 50;
+/// next page is requested once the selection gets this close to the end
+const LOOKAHEAD: usize = 10;
+const PAGE_JUMP: usize = 10;
+
+pub(crate) enum LogBrowserOutcome {
+    Browsing,
+    Close,
+    Diff(DiffRange),
+}
+
+/// revision list of one url, loaded page by page while scrolling down
+pub(crate) struct LogBrowser {
+    url: String,
+    entries: Vec<LogEntry>,
+    state: ListState,
+    loading: bool,
+    exhausted: bool,
+    /// revision marked as the other end of a range diff
+    mark: Option<u32>,
+    /// revision the first page starts at, HEAD if none
+    start: Option<u32>,
+}
+
+impl LogBrowser {
+    pub(crate) fn new(url: String, start: Option<u32>) -> Self {
+        Self {
+            url,
+            entries: vec![],
+            state: ListState::default(),
+            loading: false,
+            exhausted: false,
+            mark: None,
+            start,
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub(crate) fn selected(&self) -> Option<&LogEntry> {
+        self.state.selected().and_then(|i| self.entries.get(i))
+    }
+
+    pub(crate) fn append(&mut self, page: Vec<LogEntry>) {
+        self.loading = false;
+        self.exhausted = page.len() < LOG_PAGE_SIZE || page.last().is_none_or(|e| e.revision <= 1);
+        self.entries.extend(page);
+        if self.state.selected().is_none() && !self.entries.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    pub(crate) fn handle(&mut self, action: Action) -> LogBrowserOutcome {
+        match action {
+            Action::Cancel | Action::Back => return LogBrowserOutcome::Close,
+            Action::Down => self.down(1),
+            Action::Up => self.up(1),
+            Action::PageDown | Action::ScrollTextDown => self.down(PAGE_JUMP),
+            Action::PageUp | Action::ScrollTextUp => self.up(PAGE_JUMP),
+            Action::Top => self.up(self.entries.len()),
+            Action::Bottom => self.down(self.entries.len()),
+            Action::Mark => {
+                let rev = self.selected().map(|e| e.revision);
+                self.mark = if self.mark == rev { None } else { rev };
+            }
+            Action::Diff | Action::Open => {
+                if let Some(rev) = self.selected().map(|e| e.revision) {
+                    return LogBrowserOutcome::Diff(match self.mark {
+                        Some(mark) if mark != rev => DiffRange::Range(mark.min(rev), mark.max(rev)),
+                        _ => DiffRange::Change(rev),
+                    });
+                }
+            }
+            _ => {}
+        }
+        LogBrowserOutcome::Browsing
+    }
+
+    fn down(&mut self, by: usize) {
+        if let Some(selected) = self.state.selected() {
+            let last = self.entries.len().saturating_sub(1);
+            self.state.select(Some((selected + by).min(last)));
+        }
+    }
+
+    fn up(&mut self, by: usize) {
+        if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.saturating_sub(by)));
+        }
+    }
+
+    /// request for the next page, if the selection is close enough to the end of what's loaded
+    pub(crate) fn next_page_request(&mut self) -> Option<DataRequest> {
+        if self.loading || self.exhausted {
+            return None;
+        }
+        let selected = self.state.selected().unwrap_or_default();
+        if !self.entries.is_empty() && selected + LOOKAHEAD < self.entries.len() {
+            return None;
+        }
+        self.loading = true;
+        let from = match self.entries.last() {
+            Some(e) => Some(e.revision - 1),
+            None => self.start,
+        };
+        Some(DataRequest::LogPage(TargetUrl(self.url.clone()), from))
+    }
+
+    pub(crate) fn render<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+            .split(area);
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[1]);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|e| {
+                let mark = if self.mark == Some(e.revision) {
+                    '*'
+                } else {
+                    ' '
+                };
+                ListItem::new(Spans::from(vec![
+                    Span::styled(format!("{mark}r{:<7}", e.revision), theme::fg(Slot::Accent)),
+                    Span::styled(format!("{:<12} ", e.author), theme::fg(Slot::Ok)),
+                    Span::raw(format!("{} ", e.date.get(..10).unwrap_or(&e.date))),
+                    Span::raw(e.first_line().to_owned()),
+                ]))
+            })
+            .collect();
+        let title = if self.loading {
+            format!("log: {} (loading...)", self.url)
+        } else {
+            format!("log: {}", self.url)
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .bg(theme::color(Slot::SelectedBg)),
+            )
+            .highlight_symbol(">>");
+        frame.render_stateful_widget(list, chunks[0], &mut self.state);
+
+        let (message, paths) = match self.selected() {
+            Some(entry) => (
+                entry.to_spans(),
+                entry
+                    .paths
+                    .iter()
+                    .map(|p| {
+                        let mut spans = vec![
+                            Span::styled(
+                                format!("{} ", p.action),
+                                theme::fg(p.slot()).add_modifier(Modifier::BOLD),
+                            ),
+                            Span::raw(p.path.clone()),
+                        ];
+                        if let Some(from) = &p.copy_from {
+                            spans.push(Span::styled(
+                                format!(" (from {from})"),
+                                theme::fg(Slot::Faint),
+                            ));
+                        }
+                        ListItem::new(Spans::from(spans))
+                    })
+                    .collect(),
+            ),
+            None => (vec![], vec![]),
+        };
+        frame.render_widget(
+            Paragraph::new(message)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title("message")),
+            right_chunks[0],
+        );
+        frame.render_widget(
+            List::new(paths).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("changed paths"),
+            ),
+            right_chunks[1],
+        );
+    }
+}
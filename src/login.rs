@@ -0,0 +1,115 @@
+use crate::{
+    config::Login,
+    theme::{self, Slot},
+    ui::centered_rect,
+};
+use crossterm::event::KeyCode;
+use tui::{
+    backend::Backend,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const LOGIN: &str = "svn login : [switch: Tab], [submit: Enter], [cancel: Esc]";
+
+#[derive(PartialEq, Eq)]
+enum LoginField {
+    Username,
+    Password,
+}
+
+pub(crate) enum LoginOutcome {
+    Editing,
+    Submit(Login),
+    Cancel,
+}
+
+/// in-tui prompt asking for svn credentials
+pub(crate) struct LoginPrompt {
+    username: String,
+    password: String,
+    field: LoginField,
+}
+
+impl LoginPrompt {
+    pub(crate) fn new(username: Option<String>) -> Self {
+        let field = if username.is_some() {
+            LoginField::Password
+        } else {
+            LoginField::Username
+        };
+        Self {
+            username: username.unwrap_or_default(),
+            password: String::new(),
+            field,
+        }
+    }
+
+    pub(crate) fn handle(&mut self, code: KeyCode) -> LoginOutcome {
+        let value = match self.field {
+            LoginField::Username => &mut self.username,
+            LoginField::Password => &mut self.password,
+        };
+        match code {
+            KeyCode::Esc => return LoginOutcome::Cancel,
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                self.field = match self.field {
+                    LoginField::Username => LoginField::Password,
+                    LoginField::Password => LoginField::Username,
+                };
+            }
+            KeyCode::Enter => {
+                if self.field == LoginField::Username {
+                    self.field = LoginField::Password;
+                } else if !self.username.is_empty() {
+                    return LoginOutcome::Submit(Login {
+                        username: self.username.clone(),
+                        password: self.password.clone(),
+                    });
+                }
+            }
+            KeyCode::Backspace => {
+                value.pop();
+            }
+            KeyCode::Char(c) => value.push(c),
+            _ => {}
+        }
+        LoginOutcome::Editing
+    }
+
+    pub(crate) fn render<B: Backend>(&self, frame: &mut Frame<B>) {
+        let area = centered_rect(50, 20, frame.size());
+        let field_style = |field: LoginField| {
+            if self.field == field {
+                Style::default()
+                    .fg(theme::color(Slot::Selected))
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            }
+        };
+        let text = vec![
+            Spans::from(vec![
+                Span::styled("username: ", field_style(LoginField::Username)),
+                Span::raw(self.username.as_str()),
+            ]),
+            Spans::from(vec![
+                Span::styled("password: ", field_style(LoginField::Password)),
+                Span::raw("*".repeat(self.password.chars().count())),
+            ]),
+        ];
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(text).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(LOGIN)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            ),
+            area,
+        );
+    }
+}
@@ -1,32 +1,52 @@
+mod activity;
+mod app;
+mod blame;
+mod cache_stats;
+mod cli;
+mod commit;
+mod config;
 mod data_handler;
+mod diff;
+mod disk_cache;
+mod errors;
+mod event;
+mod finder;
+mod keymap;
 mod lister;
+mod log_browser;
+mod login;
+mod panes;
+mod revlog;
+mod search;
+mod status;
+mod text_view;
+mod theme;
 mod ui;
 
-use crate::{data_handler::*, lister::*};
+use crate::{
+    app::App,
+    cli::Cli,
+    config::{Config, LayoutConfig, ResolvedLogin},
+    data_handler::DataHandler,
+    event::AppEvent,
+    keymap::Keymap,
+    lister::CustomError,
+    login::LoginPrompt,
+    theme::Theme,
+};
+use clap::Parser;
 use crossterm::{
-    event::{poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use log::debug;
 use std::{
+    fs::File,
     io::{self, Stdout},
-    sync::{mpsc, Arc, Mutex},
-    time::Duration,
-};
-use svn_cmd::{ListEntry, PathType};
-use tui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
-    Terminal,
+    process,
+    sync::{mpsc, Arc},
 };
-
-const HUGE_FILES_SUFFIX: [&str; 9] = [
-    ".db", ".bin", ".mercury", ".o", ".obj", ".dll", ".so", ".a", ".exe",
-];
+use tui::{backend::CrosstermBackend, Terminal};
 
 struct Terminal_ {
     term: Terminal<CrosstermBackend<Stdout>>,
@@ -63,432 +83,98 @@ impl Drop for Terminal_ {
     }
 }
 
-fn main() -> Result<(), CustomError> {
-    env_logger::init();
-    ui()
-}
-
-const INITIAL_URL: &str = "https://svn.ali.global/GDK_games/GDK_games/BLS/";
-const PREV: &str = " <-- ";
-const PPREV: &str = " <---- ";
-const MIDDLE: &str = "SVN list";
-const INFO: &str = "info";
-const MESSAGES: &str = "messages";
-
-enum ScrollReq {
-    Up,
-    Down,
-}
-
-#[derive(Default)]
-struct ViewScroller {
-    size: u16,
-    current: u16,
-}
-
-impl From<u16> for ViewScroller {
-    fn from(size: u16) -> Self {
-        Self {
-            size,
-            ..Default::default()
-        }
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("svn-tui: {e}");
+        process::exit(1);
     }
 }
 
-impl ViewScroller {
-    fn handle(&mut self, height: u16, req: ScrollReq) -> u16 {
-        match req {
-            ScrollReq::Up => {
-                if self.current >= height {
-                    self.current -= height;
-                }
-            }
-            ScrollReq::Down => {
-                if self.current + height < self.size {
-                    self.current += height;
-                }
-            }
-        }
-        self.current
+fn run(cli: Cli) -> Result<(), CustomError> {
+    if cli.dump_default_keymap {
+        print!("{}", keymap::DEFAULT_KEYMAP);
+        return Ok(());
     }
-
-    fn cur(&self) -> u16 {
-        self.current
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(path) = &cli.log_file {
+        logger.target(env_logger::Target::Pipe(Box::new(File::create(path)?)));
     }
-}
-
-fn ui() -> Result<(), CustomError> {
-    let base_url = if let Ok(info_entry) = svn_helper::info(&svn_helper::new()) {
-        let mut url = info_entry.entry.url;
-        url.push('/');
-        url
-    } else {
-        INITIAL_URL.to_owned()
-    };
-    let custom_lists = Arc::new(Mutex::new(CustomLists::from(vec![CustomList::from(
-        base_url.clone(),
-    )])));
-    let mut term = Terminal_::create()?;
-    let custom_state = Arc::new(Mutex::new({
-        let custom_lists = Arc::clone(&custom_lists);
-        let locked_lists = custom_lists.lock().unwrap();
-        let CustomListsToDisplay { cur, .. } = locked_lists.get_current();
-        CustomListState::from(cur.ok_or_else(|| CustomError::NoDataToList)?)
-    }));
-    let mut new_data_request: Option<(DataRequest, ViewId)> = Some((
-        DataRequest::List(TargetUrl(base_url.clone())),
-        ViewId::MainList,
+    logger.init();
+    let config = Config::load(cli.config.as_deref())?;
+    let keymap = Keymap::new(&config.keymap)?;
+    keymap::set_hints(&keymap);
+    theme::set(Theme::load(&config.theme)?);
+    let data_handler = Arc::new(DataHandler::new(
+        cli.revision.clone(),
+        cli.no_cache,
+        config.cache.persistent,
+        config.prefetch.clone(),
     ));
-    let message = Arc::new(Mutex::new(format!("requesting svn list for '{base_url}'")));
-    let default_block = Block::default().borders(Borders::ALL);
-    let svn_info_list = Arc::new(Mutex::new(vec![]));
-    let update_svn_info_str = |entry: &ListEntry| {
-        let mut sis = svn_info_list.lock().unwrap();
-        *sis = vec![
-            ListItem::new(format!("      url: {}", entry.name)),
-            ListItem::new(format!(" revision: {}", entry.commit.revision)),
-            ListItem::new(format!("   author: {}", entry.commit.author)),
-            ListItem::new(format!("     date: {}", entry.commit.date)),
-        ];
-    };
-    let data_handler = Arc::new(DataHandler::default());
-    let (error_tx, error_rx) = mpsc::channel::<CustomError>();
-    let text_view = Arc::new(Mutex::new(Option::<(Paragraph, String)>::None));
-    let log_view = Arc::new(Mutex::new(Option::<Paragraph>::None));
-    let text_view_scroller = Arc::new(Mutex::new(Option::<ViewScroller>::None));
-    let log_view_scroller = Arc::new(Mutex::new(Option::<ViewScroller>::None));
-    let mut text_view_scroll_req: Option<ScrollReq>;
-
-    loop {
-        text_view_scroll_req = None;
-        let mut log_view_need_to_be_updated = false;
-        if poll(Duration::from_millis(200))? {
-            if let Event::Key(KeyEvent { code, .. }) = read()? {
-                svn_info_list.lock().unwrap().clear();
-                match code {
-                    KeyCode::Esc => break,
-                    KeyCode::PageUp | KeyCode::Char('u') => {
-                        text_view_scroll_req = Some(ScrollReq::Up)
-                    }
-                    KeyCode::PageDown | KeyCode::Char('d') => {
-                        text_view_scroll_req = Some(ScrollReq::Down)
-                    }
-                    KeyCode::Char('j') | KeyCode::Down => {
-                        custom_state.lock().unwrap().inc();
-                        log_view_need_to_be_updated = true;
-                    }
-                    KeyCode::Char('k') | KeyCode::Up => {
-                        custom_state.lock().unwrap().dec();
-                        log_view_need_to_be_updated = true;
-                    }
-                    KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
-                        if new_data_request.is_none() {
-                            if let CustomListsToDisplay {
-                                cur: Some(custom_list),
-                                ..
-                            } = custom_lists.lock().unwrap().get_current()
-                            {
-                                if let Some(selected) =
-                                    custom_list.get_current_selected(Arc::clone(&custom_state))
-                                {
-                                    let mut base = custom_list.base_url.clone();
-                                    base.push_str(&selected.name);
-                                    base.push('/');
-                                    if selected.kind == PathType::Dir {
-                                        debug!("requesting new data");
-                                        *message.lock().unwrap() =
-                                            format!("requesting svn list for '{base}'");
-                                        new_data_request = Some((
-                                            DataRequest::List(TargetUrl(base.clone())),
-                                            ViewId::MainList,
-                                        ));
-                                    } else {
-                                        let name = selected.name;
-                                        if HUGE_FILES_SUFFIX.iter().any(|s| name.contains(s)) {
-                                            debug!("file is not listable, so ignore: {name}");
-                                            *message.lock().unwrap() =
-                                                format!("'{name}' is not viewable");
-                                        } else {
-                                            debug!("viewing '{name}'");
-                                            *message.lock().unwrap() =
-                                                format!("opening file: '{name}'");
-                                            new_data_request = Some((
-                                                DataRequest::Text(TargetUrl(base)),
-                                                ViewId::RightInfoPane,
-                                            ));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Char('h') | KeyCode::Left => {
-                        if new_data_request.is_none() {
-                            if let CustomListsToDisplay {
-                                cur: Some(custom_list),
-                                ..
-                            } = custom_lists.lock().unwrap().go_back()
-                            {
-                                *custom_state.lock().unwrap() = CustomListState::from(custom_list);
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
+    let mut login_prompt = None;
+    match config.resolve_login(cli.username.clone()) {
+        ResolvedLogin::None => {}
+        ResolvedLogin::UsernameOnly(username) => {
+            login_prompt = Some(LoginPrompt::new(Some(username)))
         }
+        ResolvedLogin::Complete(login) => data_handler.set_login(Some(login)),
+    }
+    let base_url = cli.resolve_base_url(data_handler.login().as_ref())?;
+    data_handler.open_disk_cache(&base_url);
+    ui(
+        data_handler,
+        keymap,
+        config.layout,
+        base_url,
+        cli.working_copy(),
+        login_prompt,
+    )
+}
 
-        if log_view_need_to_be_updated {
-            if let CustomListsToDisplay {
-                cur: Some(custom_list),
-                ..
-            } = custom_lists.lock().unwrap().get_current()
-            {
-                if let Some(selected) = custom_list.get_current_selected(Arc::clone(&custom_state))
-                {
-                    let mut base = custom_list.base_url.clone();
-                    base.push_str(&selected.name);
-                    base.push('/');
-                    //new_data_request = Some((DataRequest::Log(TargetUrl(base)), ViewId::BottomLog));
+fn ui(
+    data_handler: Arc<DataHandler>,
+    keymap: Keymap,
+    layout: LayoutConfig,
+    base_url: String,
+    working_copy: Option<String>,
+    login_prompt: Option<LoginPrompt>,
+) -> Result<(), CustomError> {
+    let mut term = Terminal_::create()?;
+    let (tx, rx) = mpsc::channel();
+    event::spawn_input(tx.clone());
+    event::spawn_ticks(tx.clone());
+    let mut app = App::new(
+        data_handler,
+        keymap,
+        layout,
+        tx,
+        base_url,
+        working_copy,
+        login_prompt,
+    )?;
+    app.update();
+    term.get_int().draw(|frame| app.render(frame))?;
+
+    // the app keeps a sender for the responses, so this only ends on quit
+    for event in rx.iter() {
+        let redraw = match event {
+            AppEvent::Key(key) => {
+                if !app.handle_key(key) {
+                    break;
                 }
+                true
             }
-        }
-
-        if let Some((req, view_id)) = new_data_request {
-            let dh = Arc::clone(&data_handler);
-            let custom_lists = Arc::clone(&custom_lists);
-            let custom_state = Arc::clone(&custom_state);
-            let message = Arc::clone(&message);
-            let err_tx = error_tx.clone();
-            let text_view = Arc::clone(&text_view);
-            let _log_view = Arc::clone(&log_view);
-            let text_scroller = Arc::clone(&text_view_scroller);
-            let _log_scroller = Arc::clone(&log_view_scroller);
-            dh.request(req.clone(), view_id, move |res_resp| {
-                debug!("data received");
-                match res_resp {
-                    Ok(v) => match v {
-                        DataResponse::List(svn_list) => {
-                            *message.lock().unwrap() =
-                                format!("displaying new svn list from '{req:?}'");
-                            let new_list = CustomList::from((
-                                svn_list.clone(),
-                                TargetUrl::from(req.clone()).into(),
-                            ));
-                            custom_lists.lock().unwrap().add_new_list(new_list);
-                            if let CustomListsToDisplay {
-                                cur: Some(list), ..
-                            } = custom_lists.lock().unwrap().get_current()
-                            {
-                                *custom_state.lock().unwrap() = CustomListState::from(list);
-                            }
-                        }
-                        DataResponse::Log(_log) => {
-                            // let mut text = Vec::<Spans>::new();
-                            // let msg = log.into_iter().nth(0).unwrap().msg;
-                            // for line in msg.lines() {
-                            //     let spans = Spans::from(vec![Span::raw(line.to_owned())]);
-                            //     text.push(spans);
-                            // }
-                            // *log_scroller.lock().unwrap() =
-                            //     Some(ViewScroller::from(text.len() as u16));
-                            // let para = Paragraph::new(text);
-                            // *log_view.lock().unwrap() = Some(para);
-                        }
-                        DataResponse::Info(_info) => {}
-                        DataResponse::Text(t) => {
-                            let mut text = Vec::<Spans>::new();
-                            for line in t.lines() {
-                                let spans = Spans::from(vec![Span::raw(line.to_owned())]);
-                                text.push(spans);
-                            }
-                            *text_scroller.lock().unwrap() =
-                                Some(ViewScroller::from(text.len() as u16));
-                            let para = Paragraph::new(text);
-                            *text_view.lock().unwrap() =
-                                Some((para, TargetUrl::from(req.clone()).into()));
-                        }
-                    },
-                    Err(e) => err_tx.send(e).unwrap(),
-                }
-            });
-            debug!("out here");
-            new_data_request = None;
-        }
-        if let Ok(e) = error_rx.try_recv() {
-            return Err(e);
-        }
-
-        if let CustomListsToDisplay {
-            cur: Some(custom_list),
-            ..
-        } = custom_lists.lock().unwrap().get_current()
-        {
-            if let Some(selected) = custom_list.get_current_selected(Arc::clone(&custom_state)) {
-                update_svn_info_str(&selected);
+            AppEvent::Resize => true,
+            AppEvent::Tick => app.on_tick(),
+            AppEvent::Response(req, view_id, res_resp) => {
+                app.on_response(req, view_id, *res_resp);
+                true
             }
+            AppEvent::InputFailed(e) => return Err(e.into()),
+        };
+        app.update();
+        if redraw {
+            term.get_int().draw(|frame| app.render(frame))?;
         }
-
-        term.get_int().draw(|frame| {
-            let vertical_chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(0)
-                .constraints(
-                    [
-                        Constraint::Percentage(7),
-                        Constraint::Percentage(80),
-                        Constraint::Percentage(13),
-                    ]
-                    .as_ref(),
-                )
-                .split(frame.size());
-
-            let text_str = {
-                let locked_msg = message.lock().unwrap();
-                locked_msg.to_string()
-            };
-            let text = {
-                vec![Spans::from(Span::styled(
-                    &text_str,
-                    Style::default().fg(Color::LightMagenta),
-                ))]
-            };
-            frame.render_widget(
-                Paragraph::new(text).block(default_block.clone().title(MESSAGES)),
-                vertical_chunks[0],
-            );
-
-            let chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .margin(0)
-                .constraints(
-                    [
-                        Constraint::Percentage(15),
-                        Constraint::Percentage(15),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(50),
-                    ]
-                    .as_ref(),
-                )
-                .split(vertical_chunks[1]);
-
-            let lower_hchunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .margin(0)
-                .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-                .split(vertical_chunks[2]);
-
-            let CustomListsToDisplay { cur, prev, pprev } = {
-                let locked_lists = custom_lists.lock().unwrap();
-                locked_lists.get_current()
-            };
-
-            if let Some(pprev) = pprev {
-                frame.render_widget(
-                    List::new(pprev.get_list_items()).block(default_block.clone().title(PPREV)),
-                    chunks[0],
-                );
-            } else {
-                frame.render_widget(default_block.clone().title(PPREV), chunks[0]);
-            }
-
-            if let Some(prev) = prev {
-                frame.render_widget(
-                    List::new(prev.get_list_items()).block(default_block.clone().title("")),
-                    chunks[1],
-                );
-            } else {
-                frame.render_widget(default_block.clone().title(""), chunks[3]);
-            }
-
-            let list = {
-                let locked = svn_info_list.lock().unwrap();
-                List::new(locked.clone())
-            };
-            frame.render_widget(
-                list.block(
-                    default_block
-                        .clone()
-                        .title(INFO)
-                        .border_style(Style::default().fg(Color::LightCyan))
-                        .border_type(BorderType::Thick),
-                ),
-                lower_hchunks[0],
-            );
-            frame.render_widget(default_block.clone().title(PREV), chunks[1]);
-
-            if let Some(para) = &*log_view.lock().unwrap() {
-                frame.render_widget(
-                    para.clone().block(
-                        default_block
-                            .clone()
-                            .title("commit message : [scroll-up: '9'], [scroll-down: '0']"),
-                    ),
-                    lower_hchunks[1],
-                );
-            } else {
-                frame.render_widget(
-                    default_block.clone().title("commit message"),
-                    lower_hchunks[1],
-                );
-            }
-
-            if let Some(curr) = cur {
-                let list = List::new(curr.get_list_items())
-                    .block(
-                        default_block
-                            .clone()
-                            .title(MIDDLE)
-                            .border_style(Style::default().fg(Color::LightCyan))
-                            .border_type(BorderType::Thick),
-                    )
-                    .highlight_style(
-                        Style::default()
-                            .add_modifier(Modifier::BOLD)
-                            .fg(Color::LightYellow),
-                    )
-                    .style(Style::default().fg(Color::Blue))
-                    .highlight_symbol(">>");
-                frame.render_stateful_widget(
-                    list,
-                    chunks[2],
-                    &mut custom_state.lock().unwrap().state,
-                );
-            } else {
-                frame.render_widget(default_block.clone().title(MIDDLE), chunks[2]);
-            }
-
-            let scroll = if let Some(req) = text_view_scroll_req {
-                let rect = chunks[3];
-                if let Some(scroller) = &mut *text_view_scroller.lock().unwrap() {
-                    scroller.handle(rect.height, req)
-                } else {
-                    0
-                }
-            } else {
-                if let Some(scroller) = &*text_view_scroller.lock().unwrap() {
-                    scroller.cur()
-                } else {
-                    0
-                }
-            };
-            if let Some((para, url)) = &*text_view.lock().unwrap() {
-                frame.render_widget(default_block.clone(), chunks[3]);
-                frame.render_widget(
-                    para.clone()
-                        .block(
-                            default_block
-                                .clone()
-                                .title(url.as_ref())
-                                .border_style(Style::default().fg(Color::LightMagenta)),
-                        )
-                        .scroll((scroll, 0)),
-                    chunks[3],
-                );
-            } else {
-                frame.render_widget(default_block.clone(), chunks[3]);
-            }
-        })?;
     }
 
     Ok(())
@@ -0,0 +1,900 @@
+use crate::{
+    activity,
+    blame::{BlameOutcome, BlameView},
+    data_handler::*,
+    diff::{DiffAction, DiffRange},
+    keymap::{self, Action},
+    lister::*,
+    log_browser::LogBrowser,
+    revlog::LogEntry,
+    search::{self, ListFilter, Prompt, PromptMode, PromptOutcome, FILTER_HELP},
+    text_view::TextContent,
+    theme::{self, Slot},
+    ui::{Context, PaneId, Screenlet, Settled, UiFrame},
+};
+use crossterm::event::KeyEvent;
+use log::debug;
+use std::time::Duration;
+use tui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+};
+
+const HUGE_FILES_SUFFIX: [&str; 9] = [
+    ".db", ".bin", ".mercury", ".o", ".obj", ".dll", ".so", ".a", ".exe",
+];
+const PREV: &str = " <-- ";
+const PPREV: &str = " <---- ";
+const MIDDLE: &str = "SVN list";
+const INFO: &str = "info";
+const MESSAGES: &str = "messages";
+/// appended to the title of panes showing a cached response that is being checked
+const REVALIDATING: &str = " (revalidating...)";
+/// the selection has to rest this long before its log is fetched and its neighbours prefetched
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+pub(crate) fn log_browser_help(url: &str) -> String {
+    format!(
+        "log of '{url}' : [move: {}], [page: {}], [mark: {}], [diff: {}], [back: {}]",
+        keymap::keys(&[Action::Down, Action::Up]),
+        keymap::keys(&[Action::ScrollTextUp, Action::ScrollTextDown]),
+        keymap::key(Action::Mark),
+        keymap::key(Action::Diff),
+        keymap::key(Action::Back)
+    )
+}
+
+fn block() -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::fg(Slot::Border))
+}
+
+/// the focused pane stands out with a thick border in the `border_focused` colour
+fn focus(block: Block<'static>, focused: bool) -> Block<'static> {
+    if focused {
+        block
+            .border_style(theme::fg(Slot::BorderFocused))
+            .border_type(BorderType::Thick)
+    } else {
+        block
+    }
+}
+
+fn revalidating(ctx: &Context, req: DataRequest) -> &'static str {
+    if ctx.data_handler.is_revalidating(&req) {
+        REVALIDATING
+    } else {
+        ""
+    }
+}
+
+enum ScrollReq {
+    Up,
+    Down,
+    LineUp,
+    LineDown,
+    Top,
+    Bottom,
+}
+
+#[derive(Default)]
+struct ViewScroller {
+    size: usize,
+    current: usize,
+}
+
+impl From<usize> for ViewScroller {
+    fn from(size: usize) -> Self {
+        Self {
+            size,
+            ..Default::default()
+        }
+    }
+}
+
+impl ViewScroller {
+    fn handle(&mut self, height: u16, req: ScrollReq) -> usize {
+        let height = usize::from(height);
+        match req {
+            ScrollReq::Up => {
+                if self.current >= height {
+                    self.current -= height;
+                }
+            }
+            ScrollReq::Down => {
+                if self.current + height < self.size {
+                    self.current += height;
+                }
+            }
+            ScrollReq::LineUp => self.current = self.current.saturating_sub(1),
+            ScrollReq::LineDown => {
+                if self.current + 1 < self.size {
+                    self.current += 1;
+                }
+            }
+            ScrollReq::Top => self.current = 0,
+            ScrollReq::Bottom => self.current = self.size.saturating_sub(height),
+        }
+        self.current
+    }
+
+    fn cur(&self) -> usize {
+        self.current
+    }
+
+    fn jump(&mut self, line: usize) {
+        self.current = line.min(self.size.saturating_sub(1));
+    }
+}
+
+/// scroll position of a view after applying the pending scroll request, if any
+fn scroll_offset(
+    scroller: &mut Option<ViewScroller>,
+    height: u16,
+    req: Option<ScrollReq>,
+) -> usize {
+    match (scroller, req) {
+        (Some(scroller), Some(req)) => scroller.handle(height, req),
+        (Some(scroller), None) => scroller.cur(),
+        (None, _) => 0,
+    }
+}
+
+pub(crate) fn diff_action(action: Action) -> Option<DiffAction> {
+    match action {
+        Action::NextHunk => Some(DiffAction::NextHunk),
+        Action::PrevHunk => Some(DiffAction::PrevHunk),
+        Action::NextFile => Some(DiffAction::NextFile),
+        Action::PrevFile => Some(DiffAction::PrevFile),
+        Action::ToggleDiffMode => Some(DiffAction::ToggleMode),
+        _ => None,
+    }
+}
+
+/// the line at the top: the latest message, error or retry, and what's running
+pub(crate) struct MessagesPane;
+
+impl Screenlet for MessagesPane {
+    fn id(&self) -> PaneId {
+        PaneId::Messages
+    }
+
+    fn event(&mut self, _action: Action, _ctx: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, _focused: bool, ctx: &mut Context) {
+        let (text, slot) = match (ctx.error_log.banner(), ctx.data_handler.retrying()) {
+            (Some(banner), _) => (banner, Slot::Error),
+            (None, Some(retrying)) => (retrying, Slot::Warning),
+            (None, None) => (ctx.message.clone(), Slot::Message),
+        };
+        let title = format!(
+            "{MESSAGES} : {} [activity: {}], [focus: {}], [zoom: {}], [quit: {}]",
+            activity::status_line(&ctx.data_handler.activity(), ctx.tick),
+            keymap::key(Action::Activity),
+            keymap::key(Action::FocusNext),
+            keymap::key(Action::Zoom),
+            keymap::key(Action::Quit)
+        );
+        frame.render_widget(
+            Paragraph::new(Spans::from(Span::styled(text, theme::fg(slot))))
+                .block(block().title(title)),
+            area,
+        );
+    }
+}
+
+/// one of the parent lists, left of the current one
+pub(crate) struct ParentPane {
+    /// 1 for the parent, 2 for the grandparent
+    up: usize,
+}
+
+impl ParentPane {
+    pub(crate) fn new(up: usize) -> Self {
+        Self { up }
+    }
+}
+
+impl Screenlet for ParentPane {
+    fn id(&self) -> PaneId {
+        if self.up == 1 {
+            PaneId::Prev
+        } else {
+            PaneId::Pprev
+        }
+    }
+
+    fn event(&mut self, _action: Action, _ctx: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, _focused: bool, ctx: &mut Context) {
+        let CustomListsToDisplay { prev, pprev, .. } = ctx.lists.get_current();
+        let (list, title) = if self.up == 1 {
+            (prev, PREV)
+        } else {
+            (pprev, PPREV)
+        };
+        let items = list
+            .as_ref()
+            .map(|l| l.get_list_items())
+            .unwrap_or_default();
+        frame.render_widget(List::new(items).block(block().title(title)), area);
+    }
+}
+
+/// the directory being browsed
+pub(crate) struct ListPane {
+    /// url of the selected entry, its neighbours are prefetched once it settles
+    selection: Settled<Option<String>>,
+    /// entries shown at once, as of the last draw; a page up or down moves this far
+    page: u16,
+    /// a search or filter being typed
+    prompt: Option<Prompt>,
+    /// selection and filter from before the prompt, brought back if it is cancelled
+    origin: Option<usize>,
+    origin_filter: Option<ListFilter>,
+    /// the last search, for 'n' and 'N'
+    search: Option<ListFilter>,
+}
+
+impl ListPane {
+    pub(crate) fn new() -> Self {
+        Self {
+            selection: Settled::new(None),
+            page: 1,
+            prompt: None,
+            origin: None,
+            origin_filter: None,
+            search: None,
+        }
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt, ctx: &mut Context) {
+        ctx.message = match prompt.mode {
+            PromptMode::Search => search::search_help(),
+            PromptMode::Filter => FILTER_HELP.to_owned(),
+        };
+        self.origin = ctx.list_state.get();
+        self.origin_filter = ctx
+            .lists
+            .get_current()
+            .cur
+            .and_then(|l| l.filter().cloned());
+        self.prompt = Some(prompt);
+    }
+}
+
+/// moves the selection to the nearest entry whose name matches, going round at the end of the
+/// list; the selected entry itself only counts if `from_selected`, and comes last otherwise
+fn select_match(
+    ctx: &mut Context,
+    search: &ListFilter,
+    forward: bool,
+    from_selected: bool,
+) -> bool {
+    let Some(list) = ctx.lists.get_current().cur else {
+        return false;
+    };
+    let entries = list.entries();
+    let len = entries.len();
+    let selected = ctx.list_state.get().unwrap_or(0);
+    let skip = usize::from(!from_selected);
+    let found = (skip..len + skip)
+        .map(|i| {
+            if forward {
+                (selected + i) % len
+            } else {
+                (selected + len - i % len) % len
+            }
+        })
+        .find(|i| search.matches(&entries[*i].name));
+    ctx.list_state.select(found);
+    found.is_some()
+}
+
+/// filters the current list; the selected entry stays selected if it is still shown
+fn set_filter(ctx: &mut Context, filter: Option<ListFilter>) {
+    let Some(mut list) = ctx.lists.get_current().cur else {
+        return;
+    };
+    let selected = list.get_current_selected(&ctx.list_state).map(|e| e.name);
+    list.set_filter(filter);
+    let index = selected.and_then(|name| list.entries().iter().position(|e| e.name == name));
+    ctx.lists.replace_current(list.clone());
+    ctx.list_state = CustomListState::from(list);
+    ctx.list_state.select(index);
+}
+
+impl Screenlet for ListPane {
+    fn id(&self) -> PaneId {
+        PaneId::List
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::MainList)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn captures(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    fn key(&mut self, key: KeyEvent, ctx: &mut Context) -> bool {
+        let Some(prompt) = &mut self.prompt else {
+            return false;
+        };
+        let outcome = prompt.handle(key.code);
+        let parsed = prompt.parse();
+        let mode = prompt.mode;
+        let done = matches!(outcome, PromptOutcome::Done);
+        match (mode, outcome) {
+            (_, PromptOutcome::Cancel) => {
+                if mode == PromptMode::Filter {
+                    set_filter(ctx, self.origin_filter.take());
+                }
+                ctx.list_state.select(self.origin);
+                ctx.message = match mode {
+                    PromptMode::Search => "search cancelled",
+                    PromptMode::Filter => "filter cancelled",
+                }
+                .to_owned();
+                self.prompt = None;
+            }
+            // the selection jumps to the first match while typing, counting from where it was
+            (PromptMode::Search, _) => {
+                ctx.list_state.select(self.origin);
+                if let Ok(Some(search)) = parsed {
+                    if !select_match(ctx, &search, true, true) {
+                        ctx.message = format!("no match for {search}");
+                    }
+                    if done {
+                        self.search = Some(search);
+                    }
+                }
+                if done {
+                    self.prompt = None;
+                }
+            }
+            // an invalid pattern leaves the list as it was, and can't be applied
+            (PromptMode::Filter, _) => {
+                if let Ok(filter) = parsed {
+                    set_filter(ctx, filter);
+                    if done {
+                        self.prompt = None;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn event(&mut self, action: Action, ctx: &mut Context) -> bool {
+        match action {
+            Action::Search => self.open_prompt(Prompt::search(), ctx),
+            Action::Filter => {
+                let current = ctx.lists.get_current().cur;
+                let prompt = Prompt::filter(current.as_ref().and_then(|l| l.filter()));
+                self.open_prompt(prompt, ctx);
+            }
+            Action::NextMatch | Action::PrevMatch => match &self.search {
+                Some(search) => {
+                    if !select_match(ctx, search, action == Action::NextMatch, false) {
+                        ctx.message = format!("no match for {search}");
+                    }
+                }
+                None => {
+                    ctx.message = format!(
+                        "nothing searched for yet: [search: {}]",
+                        keymap::key(Action::Search)
+                    )
+                }
+            },
+            Action::Down => ctx.list_state.inc(),
+            Action::Up => ctx.list_state.dec(),
+            Action::PageDown => (0..self.page).for_each(|_| ctx.list_state.inc()),
+            Action::PageUp => (0..self.page).for_each(|_| ctx.list_state.dec()),
+            Action::Top => ctx.list_state.select(Some(0)),
+            Action::Bottom => {
+                if let Some(list) = ctx.lists.get_current().cur {
+                    ctx.list_state
+                        .select(Some(list.entries().len().saturating_sub(1)));
+                }
+            }
+            Action::Refresh => {
+                if let CustomListsToDisplay {
+                    cur: Some(custom_list),
+                    ..
+                } = ctx.lists.get_current()
+                {
+                    let req = DataRequest::List(TargetUrl(custom_list.base_url));
+                    ctx.data_handler.forget(&req);
+                    ctx.message = format!("refreshing {req}");
+                    ctx.new_data_request = Some((req, ViewId::MainList));
+                }
+            }
+            Action::Blame => match ctx.selected() {
+                Some((_, selected)) if selected.kind == PathType::Dir => {
+                    ctx.message = "blame needs a file to be selected".to_owned();
+                }
+                Some((base_url, selected)) => {
+                    let name = selected.name;
+                    if HUGE_FILES_SUFFIX.iter().any(|s| name.contains(s)) {
+                        ctx.message = format!("'{name}' is not viewable");
+                    } else {
+                        let url = format!("{base_url}{name}");
+                        ctx.message = format!("requesting blame for '{url}'");
+                        ctx.new_data_request = Some((
+                            DataRequest::Blame(TargetUrl(url), None),
+                            ViewId::RightInfoPane,
+                        ));
+                    }
+                }
+                None => {}
+            },
+            Action::Diff => {
+                if let Some((base_url, selected)) = ctx.selected() {
+                    let url = format!("{base_url}{}", selected.name);
+                    let range = DiffRange::Change(selected.commit.revision);
+                    ctx.message = format!("requesting {range} for '{url}'");
+                    ctx.new_data_request = Some((
+                        DataRequest::Diff(TargetUrl(url), range),
+                        ViewId::RightInfoPane,
+                    ));
+                }
+            }
+            Action::Open => {
+                if ctx.new_data_request.is_none() {
+                    if let Some((base_url, selected)) = ctx.selected() {
+                        let mut base = base_url;
+                        base.push_str(&selected.name);
+                        base.push('/');
+                        if selected.kind == PathType::Dir {
+                            debug!("requesting new data");
+                            ctx.message = format!("requesting svn list for '{base}'");
+                            ctx.new_data_request =
+                                Some((DataRequest::List(TargetUrl(base)), ViewId::MainList));
+                        } else {
+                            let name = selected.name;
+                            if HUGE_FILES_SUFFIX.iter().any(|s| name.contains(s)) {
+                                debug!("file is not listable, so ignore: {name}");
+                                ctx.message = format!("'{name}' is not viewable");
+                            } else {
+                                debug!("viewing '{name}'");
+                                ctx.message = format!("opening file: '{name}'");
+                                ctx.new_data_request = Some((
+                                    DataRequest::Text(TargetUrl(base)),
+                                    ViewId::RightInfoPane,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+            Action::Back => {
+                if ctx.new_data_request.is_none() {
+                    if let CustomListsToDisplay {
+                        cur: Some(custom_list),
+                        ..
+                    } = ctx.lists.go_back()
+                    {
+                        ctx.list_state = CustomListState::from(custom_list);
+                    }
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn response(&mut self, req: DataRequest, response: DataResponse, ctx: &mut Context) {
+        let (svn_list, revalidated) = match response {
+            DataResponse::List(svn_list) => (svn_list, false),
+            DataResponse::Refreshed(response) => match *response {
+                DataResponse::List(svn_list) => (svn_list, true),
+                _ => return,
+            },
+            _ => return,
+        };
+        let mut new_list = CustomList::from((svn_list, TargetUrl::from(req).into()));
+        // a revalidated list comes in again, it replaces the stale copy and keeps its filter
+        let stale = ctx
+            .lists
+            .get_current()
+            .cur
+            .filter(|cur| cur.base_url == new_list.base_url);
+        // unless the user left it in the meantime, the list isn't on screen to be refreshed
+        if revalidated && stale.is_none() {
+            debug!("dropping the refreshed list of '{}'", new_list.base_url);
+            return;
+        }
+        ctx.message = format!("displaying new svn list from '{}'", new_list.base_url);
+        let refreshed = stale.is_some();
+        if let Some(stale) = stale {
+            new_list.set_filter(stale.filter().cloned());
+            ctx.lists.replace_current(new_list);
+        } else {
+            ctx.lists.add_new_list(new_list);
+        }
+        if let CustomListsToDisplay {
+            cur: Some(list), ..
+        } = ctx.lists.get_current()
+        {
+            let selected = ctx.list_state.get().filter(|_| refreshed);
+            ctx.list_state = CustomListState::from(list);
+            ctx.list_state.select(selected);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context) {
+        let selected = ctx
+            .selected()
+            .map(|(base_url, selected)| format!("{base_url}{}", selected.name));
+        self.selection.set(selected);
+        // the selection has settled, its directory is likely to be entered next
+        if self.selection.take(SETTLE_DELAY).is_some() {
+            if let (
+                CustomListsToDisplay {
+                    cur: Some(custom_list),
+                    ..
+                },
+                Some(selected),
+            ) = (ctx.lists.get_current(), ctx.list_state.get())
+            {
+                ctx.data_handler
+                    .prefetch(&custom_list.base_url, custom_list.entries(), selected);
+            }
+        }
+    }
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, focused: bool, ctx: &mut Context) {
+        // inside the borders
+        self.page = area.height.saturating_sub(2).max(1);
+        let Some(curr) = ctx.lists.get_current().cur else {
+            frame.render_widget(focus(block().title(MIDDLE), focused), area);
+            return;
+        };
+        let filter = curr
+            .filter()
+            .map_or_else(String::new, |f| format!(" [filter: {f}]"));
+        let title = format!(
+            "{MIDDLE}{}{filter}",
+            revalidating(ctx, DataRequest::List(TargetUrl(curr.base_url.clone())))
+        );
+        let list = List::new(curr.get_list_items())
+            .block(focus(
+                block()
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+                focused,
+            ))
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(theme::color(Slot::Selected)),
+            )
+            .highlight_symbol(">>");
+        frame.render_stateful_widget(list, area, &mut ctx.list_state.state);
+        // over the last entries of the list
+        if let Some(prompt) = &self.prompt {
+            let height = area.height.min(3);
+            let line = Rect {
+                y: area.bottom() - height,
+                height,
+                ..area
+            };
+            prompt.render(frame, line);
+        }
+    }
+}
+
+/// revision, author and date of the selected entry
+pub(crate) struct InfoPane;
+
+impl Screenlet for InfoPane {
+    fn id(&self) -> PaneId {
+        PaneId::Info
+    }
+
+    fn event(&mut self, _action: Action, _ctx: &mut Context) -> bool {
+        false
+    }
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, _focused: bool, ctx: &mut Context) {
+        let items: Vec<ListItem> = ctx
+            .selected()
+            .map(|(_, entry)| {
+                vec![
+                    ListItem::new(format!("      url: {}", entry.name)),
+                    ListItem::new(format!(" revision: {}", entry.commit.revision)),
+                    ListItem::new(format!("   author: {}", entry.commit.author)),
+                    ListItem::new(format!("     date: {}", entry.commit.date)),
+                ]
+            })
+            .unwrap_or_default();
+        frame.render_widget(
+            List::new(items).block(
+                block()
+                    .title(INFO)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            ),
+            area,
+        );
+    }
+}
+
+/// last commit message of the selected entry
+pub(crate) struct CommitMessagePane {
+    /// fetched once the selection rests for `SETTLE_DELAY`
+    selection: Settled<Option<String>>,
+    view: Option<Paragraph<'static>>,
+    scroller: Option<ViewScroller>,
+    scroll_req: Option<ScrollReq>,
+}
+
+impl CommitMessagePane {
+    pub(crate) fn new() -> Self {
+        Self {
+            selection: Settled::new(None),
+            view: None,
+            scroller: None,
+            scroll_req: None,
+        }
+    }
+}
+
+impl Screenlet for CommitMessagePane {
+    fn id(&self) -> PaneId {
+        PaneId::CommitMessage
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::BottomLog)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn event(&mut self, action: Action, _ctx: &mut Context) -> bool {
+        self.scroll_req = Some(match action {
+            Action::ScrollMessageUp | Action::PageUp => ScrollReq::Up,
+            Action::ScrollMessageDown | Action::PageDown => ScrollReq::Down,
+            Action::Up => ScrollReq::LineUp,
+            Action::Down => ScrollReq::LineDown,
+            Action::Top => ScrollReq::Top,
+            Action::Bottom => ScrollReq::Bottom,
+            _ => return false,
+        });
+        true
+    }
+
+    fn response(&mut self, req: DataRequest, response: DataResponse, _ctx: &mut Context) {
+        if let DataResponse::Log(log) = response {
+            // the log of an entry the selection already moved away from
+            let url = String::from(TargetUrl::from(req));
+            if self.selection.value().as_deref() != Some(url.as_str()) {
+                debug!("dropping the log of '{url}'");
+                return;
+            }
+            let text = log.first().map(LogEntry::to_spans).unwrap_or_default();
+            self.scroller = Some(ViewScroller::from(text.len()));
+            self.view = Some(Paragraph::new(text));
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context) {
+        let selected = ctx
+            .selected()
+            .map(|(base_url, selected)| format!("{base_url}{}", selected.name));
+        if self.selection.set(selected) {
+            self.view = None;
+            self.scroller = None;
+        }
+        if ctx.new_data_request.is_none() {
+            if let Some(Some(url)) = self.selection.take(SETTLE_DELAY) {
+                ctx.new_data_request =
+                    Some((DataRequest::Log(TargetUrl(url.clone())), ViewId::BottomLog));
+            }
+        }
+    }
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, focused: bool, ctx: &mut Context) {
+        let Some(para) = &self.view else {
+            frame.render_widget(focus(block().title("commit message"), focused), area);
+            return;
+        };
+        let mut title = format!(
+            "commit message : [scroll-up: {}], [scroll-down: {}]",
+            keymap::key(Action::ScrollMessageUp),
+            keymap::key(Action::ScrollMessageDown)
+        );
+        if let Some(url) = self.selection.value() {
+            title.push_str(revalidating(ctx, DataRequest::Log(TargetUrl(url.clone()))));
+        }
+        let scroll = scroll_offset(&mut self.scroller, area.height, self.scroll_req.take());
+        frame.render_widget(
+            para.clone()
+                .block(focus(block().title(title), focused))
+                .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0)),
+            area,
+        );
+    }
+}
+
+/// file contents, diffs and blame, on the right
+pub(crate) struct TextPane {
+    /// with its title; plain text is titled with its url
+    content: Option<(TextContent, String)>,
+    scroller: Option<ViewScroller>,
+    /// applied on the next draw, when the height of the pane is known
+    scroll_req: Option<ScrollReq>,
+}
+
+impl TextPane {
+    pub(crate) fn new() -> Self {
+        Self {
+            content: None,
+            scroller: None,
+            scroll_req: None,
+        }
+    }
+
+    fn show(&mut self, content: TextContent, title: String) {
+        self.scroller = Some(ViewScroller::from(content.rows()));
+        self.content = Some((content, title));
+    }
+}
+
+impl Screenlet for TextPane {
+    fn id(&self) -> PaneId {
+        PaneId::Text
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(ViewId::RightInfoPane)
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    /// blame has actions of its own, like a view of its own
+    fn only_focused(&self) -> bool {
+        matches!(self.content, Some((TextContent::Blame(_), _)))
+    }
+
+    fn event(&mut self, action: Action, ctx: &mut Context) -> bool {
+        if let Some(diff_action) = diff_action(action) {
+            if let (Some((TextContent::Diff(diff), _)), Some(scroller)) =
+                (&mut self.content, &mut self.scroller)
+            {
+                if let Some(row) = diff.apply(scroller.cur(), diff_action) {
+                    scroller.size = diff.rows();
+                    scroller.jump(row);
+                }
+            }
+            return true;
+        }
+        if let Some((TextContent::Blame(blame), _)) = &mut self.content {
+            let url = blame.url().to_owned();
+            let Some(outcome) = blame.handle(action) else {
+                return false;
+            };
+            match outcome {
+                BlameOutcome::Browsing => {}
+                BlameOutcome::Close => self.content = None,
+                BlameOutcome::ShowRevision(rev) => {
+                    ctx.message = log_browser_help(&url);
+                    ctx.open_log_browser = Some(LogBrowser::new(url.clone(), Some(rev)));
+                    ctx.new_data_request = Some((
+                        DataRequest::Diff(TargetUrl(url), DiffRange::Change(rev)),
+                        ViewId::RightInfoPane,
+                    ));
+                }
+                BlameOutcome::Reblame(rev) => {
+                    ctx.message = format!("requesting blame for '{url}' as of r{rev}");
+                    ctx.new_data_request = Some((
+                        DataRequest::Blame(TargetUrl(url), Some(rev)),
+                        ViewId::RightInfoPane,
+                    ));
+                }
+            }
+            return true;
+        }
+        match action {
+            Action::PageUp | Action::ScrollTextUp => self.scroll_req = Some(ScrollReq::Up),
+            Action::PageDown | Action::ScrollTextDown => self.scroll_req = Some(ScrollReq::Down),
+            Action::Up => self.scroll_req = Some(ScrollReq::LineUp),
+            Action::Down => self.scroll_req = Some(ScrollReq::LineDown),
+            Action::Top => self.scroll_req = Some(ScrollReq::Top),
+            Action::Bottom => self.scroll_req = Some(ScrollReq::Bottom),
+            Action::ReloadFile => match &self.content {
+                Some((TextContent::Plain(_), url)) => {
+                    let req = DataRequest::Text(TargetUrl(url.clone()));
+                    ctx.data_handler.forget(&req);
+                    ctx.message = format!("refreshing {req}");
+                    ctx.new_data_request = Some((req, ViewId::RightInfoPane));
+                }
+                _ => ctx.message = "no file is open".to_owned(),
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn response(&mut self, req: DataRequest, response: DataResponse, ctx: &mut Context) {
+        match response {
+            DataResponse::Text(t) => {
+                let text: Vec<Spans> = t
+                    .lines()
+                    .map(|line| Spans::from(vec![Span::raw(line.to_owned())]))
+                    .collect();
+                self.show(TextContent::Plain(text), TargetUrl::from(req).into());
+            }
+            DataResponse::Blame(lines) => {
+                let rev = match &req {
+                    DataRequest::Blame(_, rev) => *rev,
+                    _ => None,
+                };
+                let blame = BlameView::new(TargetUrl::from(req).into(), rev, lines);
+                let title = blame.title();
+                self.show(TextContent::Blame(blame), title);
+                ctx.focus = Some(PaneId::Text);
+            }
+            DataResponse::Diff(diff) => {
+                let title = match &req {
+                    DataRequest::Diff(TargetUrl(url), range) => format!(
+                        "{range} {url} : [hunk: {}], [file: {}], [side-by-side: {}]",
+                        keymap::keys(&[Action::NextHunk, Action::PrevHunk]),
+                        keymap::keys(&[Action::NextFile, Action::PrevFile]),
+                        keymap::key(Action::ToggleDiffMode)
+                    ),
+                    DataRequest::BaseDiff(paths) => {
+                        format!("diff BASE of {} path(s) to commit", paths.len())
+                    }
+                    _ => TargetUrl::from(req).into(),
+                };
+                self.show(TextContent::Diff(diff), title);
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, focused: bool, ctx: &mut Context) {
+        let scroll = scroll_offset(&mut self.scroller, area.height, self.scroll_req.take());
+        let Some((content, title)) = &mut self.content else {
+            frame.render_widget(focus(block(), focused), area);
+            return;
+        };
+        let title = match content {
+            TextContent::Plain(_) => format!(
+                "{title}{}",
+                revalidating(ctx, DataRequest::Text(TargetUrl(title.clone())))
+            ),
+            _ => title.clone(),
+        };
+        frame.render_widget(block(), area);
+        content.render(
+            frame,
+            area,
+            focus(
+                block()
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderText)),
+                focused,
+            ),
+            scroll,
+        );
+    }
+}
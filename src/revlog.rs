@@ -0,0 +1,175 @@
+use crate::theme::{self, Slot};
+use serde::{Deserialize, Serialize};
+use tui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+};
+
+/// one revision as printed by `svn log`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct LogEntry {
+    pub(crate) revision: u32,
+    pub(crate) author: String,
+    pub(crate) date: String,
+    pub(crate) msg: String,
+    pub(crate) paths: Vec<ChangedPath>,
+}
+
+/// one line of the "Changed paths" section of `svn log -v`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ChangedPath {
+    pub(crate) action: char,
+    pub(crate) path: String,
+    pub(crate) copy_from: Option<String>,
+}
+
+impl LogEntry {
+    pub(crate) fn first_line(&self) -> &str {
+        self.msg.lines().next().unwrap_or_default()
+    }
+
+    pub(crate) fn to_spans(&self) -> Vec<Spans<'static>> {
+        let mut text = vec![
+            Spans::from(Span::styled(
+                format!("r{} | {} | {}", self.revision, self.author, self.date),
+                Style::default()
+                    .fg(theme::color(Slot::Accent))
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Spans::default(),
+        ];
+        text.extend(self.msg.lines().map(|l| Spans::from(l.to_owned())));
+        text
+    }
+}
+
+impl ChangedPath {
+    pub(crate) fn slot(&self) -> Slot {
+        match self.action {
+            'A' => Slot::Added,
+            'D' => Slot::Deleted,
+            'R' => Slot::Replaced,
+            _ => Slot::Modified,
+        }
+    }
+}
+
+/// parses the plain text output of `svn log`, with or without `-v`
+pub(crate) fn parse(text: &str) -> Vec<LogEntry> {
+    let mut entries = vec![];
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some((mut entry, msg_lines)) = parse_header(line) else {
+            continue;
+        };
+        if lines.peek() == Some(&"Changed paths:") {
+            lines.next();
+            while let Some(path_line) = lines.next_if(|l| !l.trim().is_empty()) {
+                entry.paths.extend(parse_changed_path(path_line));
+            }
+        }
+        // blank line between header and message
+        lines.next();
+        entry.msg = lines
+            .by_ref()
+            .take(msg_lines)
+            .collect::<Vec<_>>()
+            .join("\n");
+        entries.push(entry);
+    }
+    entries
+}
+
+/// "r123 | author | 2023-01-31 10:00:00 +0000 (Tue, 31 Jan 2023) | 2 lines"
+fn parse_header(line: &str) -> Option<(LogEntry, usize)> {
+    let parts: Vec<&str> = line.split(" | ").collect();
+    if parts.len() != 4 {
+        return None;
+    }
+    let revision = parts[0].strip_prefix('r')?.parse().ok()?;
+    let msg_lines = parts[3].split_whitespace().next()?.parse().ok()?;
+    let date = parts[2]
+        .split_once(" (")
+        .map_or(parts[2], |(date, _)| date)
+        .to_owned();
+    Some((
+        LogEntry {
+            revision,
+            author: parts[1].to_owned(),
+            date,
+            ..Default::default()
+        },
+        msg_lines,
+    ))
+}
+
+/// "   A /trunk/new.txt (from /trunk/old.txt:122)"
+fn parse_changed_path(line: &str) -> Option<ChangedPath> {
+    let line = line.trim_start();
+    let mut chars = line.chars();
+    let action = chars.next()?;
+    let rest = chars.as_str().trim();
+    let (path, copy_from) = match rest.split_once(" (from ") {
+        Some((path, from)) => (path, Some(from.trim_end_matches(')').to_owned())),
+        None => (rest, None),
+    };
+    Some(ChangedPath {
+        action,
+        path: path.to_owned(),
+        copy_from,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = "------------------------------------------------------------------------";
+
+    #[test]
+    fn parse_plain_log() {
+        let text = format!(
+            "{LINE}\nr12 | alice | 2023-01-31 10:00:00 +0000 (Tue, 31 Jan 2023) | 3 lines\n\n\
+             fix the build\n\nand a second paragraph\n{LINE}\n\
+             r11 | bob | 2023-01-30 09:00:00 +0000 (Mon, 30 Jan 2023) | 1 line\n\nfirst\n{LINE}\n"
+        );
+        let entries = parse(&text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].revision, 12);
+        assert_eq!(entries[0].author, "alice");
+        assert_eq!(entries[0].date, "2023-01-31 10:00:00 +0000");
+        assert_eq!(entries[0].msg, "fix the build\n\nand a second paragraph");
+        assert_eq!(entries[0].first_line(), "fix the build");
+        assert_eq!(entries[1].revision, 11);
+        assert_eq!(entries[1].msg, "first");
+        assert!(entries[1].paths.is_empty());
+    }
+
+    #[test]
+    fn parse_changed_paths() {
+        let text = format!(
+            "{LINE}\nr5 | carol | 2023-02-01 12:00:00 +0000 (Wed, 01 Feb 2023) | 1 line\n\
+             Changed paths:\n   M /trunk/a.txt\n   A /trunk/new.txt (from /trunk/old.txt:4)\n   \
+             D /trunk/old.txt\n\nmove it\n{LINE}\n"
+        );
+        let entries = parse(&text);
+        assert_eq!(entries.len(), 1);
+        let paths = &entries[0].paths;
+        assert_eq!(paths.len(), 3);
+        assert_eq!(
+            (paths[0].action, paths[0].path.as_str()),
+            ('M', "/trunk/a.txt")
+        );
+        assert_eq!(paths[1].path, "/trunk/new.txt");
+        assert_eq!(paths[1].copy_from.as_deref(), Some("/trunk/old.txt:4"));
+        assert_eq!(paths[2].action, 'D');
+        assert_eq!(entries[0].msg, "move it");
+    }
+
+    #[test]
+    fn parse_skips_other_lines() {
+        assert!(parse("svn: E160013: path not found\n").is_empty());
+        assert!(parse_header("r1 | x | y").is_none());
+        assert!(parse_header("rx | a | b | 1 line").is_none());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    keymap::{self, Action},
+    lister::CustomError,
+    theme::{self, Slot},
+    ui::UiFrame,
+};
+use crossterm::event::KeyCode;
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+use tui::{
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+pub(crate) const FILTER_HELP: &str =
+    "filter : [syntax: Tab], [apply: Enter], [cancel: Esc]; an empty filter shows everything";
+
+/// Enter and Esc belong to the search line, like the keys of any text field
+pub(crate) fn search_help() -> String {
+    format!(
+        "search : [keep: Enter], [cancel: Esc], then [next: {}]",
+        keymap::key(Action::NextMatch)
+    )
+}
+
+/// how a pattern is read
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum FilterKind {
+    /// anywhere in the name, ignoring case
+    #[default]
+    Substring,
+    /// the whole name, with `*`, `?` and `[...]`
+    Glob,
+    Regex,
+}
+
+impl FilterKind {
+    fn next(self) -> Self {
+        match self {
+            FilterKind::Substring => FilterKind::Glob,
+            FilterKind::Glob => FilterKind::Regex,
+            FilterKind::Regex => FilterKind::Substring,
+        }
+    }
+}
+
+impl fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterKind::Substring => write!(f, "substring"),
+            FilterKind::Glob => write!(f, "glob"),
+            FilterKind::Regex => write!(f, "regex"),
+        }
+    }
+}
+
+/// a pattern names are matched against, for searching and filtering lists
+#[derive(Clone, Debug)]
+pub(crate) struct ListFilter {
+    pub(crate) kind: FilterKind,
+    pub(crate) pattern: String,
+    regex: Regex,
+}
+
+impl ListFilter {
+    pub(crate) fn new(kind: FilterKind, pattern: &str) -> Result<Self, CustomError> {
+        let regex = match kind {
+            FilterKind::Substring => RegexBuilder::new(&regex::escape(pattern))
+                .case_insensitive(true)
+                .build()?,
+            FilterKind::Glob => Regex::new(&glob_to_regex(pattern))?,
+            FilterKind::Regex => Regex::new(pattern)?,
+        };
+        Ok(Self {
+            kind,
+            pattern: pattern.to_owned(),
+            regex,
+        })
+    }
+
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl fmt::Display for ListFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.kind, self.pattern)
+    }
+}
+
+/// `*` matches any characters, `?` any one and `[...]` one of a set, or `[!...]` one not in it
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            // without a closing bracket it is just a bracket
+            '[' => match chars.as_str().split_once(']') {
+                Some((set, rest)) => {
+                    regex.push('[');
+                    let set = match set.strip_prefix('!') {
+                        Some(set) => {
+                            regex.push('^');
+                            set
+                        }
+                        None => set,
+                    };
+                    for c in set.chars() {
+                        // special inside a set of the regex crate, but not of a glob
+                        if matches!(c, '\\' | '[' | '&' | '~') {
+                            regex.push('\\');
+                        }
+                        regex.push(c);
+                    }
+                    regex.push(']');
+                    chars = rest.chars();
+                }
+                None => regex.push_str(r"\["),
+            },
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PromptMode {
+    Search,
+    Filter,
+}
+
+pub(crate) enum PromptOutcome {
+    Editing,
+    Done,
+    Cancel,
+}
+
+/// the line a search or a filter is typed in
+pub(crate) struct Prompt {
+    pub(crate) mode: PromptMode,
+    kind: FilterKind,
+    input: String,
+    /// why the input isn't a pattern, shown next to it
+    error: Option<String>,
+}
+
+impl Prompt {
+    pub(crate) fn search() -> Self {
+        Self {
+            mode: PromptMode::Search,
+            kind: FilterKind::Substring,
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    /// starts from the filter in place, to be edited
+    pub(crate) fn filter(current: Option<&ListFilter>) -> Self {
+        Self {
+            mode: PromptMode::Filter,
+            kind: current.map_or_else(FilterKind::default, |f| f.kind),
+            input: current.map_or_else(String::new, |f| f.pattern.clone()),
+            error: None,
+        }
+    }
+
+    pub(crate) fn handle(&mut self, code: KeyCode) -> PromptOutcome {
+        match code {
+            KeyCode::Esc => return PromptOutcome::Cancel,
+            KeyCode::Enter => return PromptOutcome::Done,
+            KeyCode::Tab if self.mode == PromptMode::Filter => self.kind = self.kind.next(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+        PromptOutcome::Editing
+    }
+
+    /// what was typed so far, None while nothing is; an invalid pattern also shows its error
+    pub(crate) fn parse(&mut self) -> Result<Option<ListFilter>, CustomError> {
+        let filter = (!self.input.is_empty())
+            .then(|| ListFilter::new(self.kind, &self.input))
+            .transpose();
+        self.error = filter.as_ref().err().map(|e| e.to_string());
+        filter
+    }
+
+    pub(crate) fn render(&self, frame: &mut UiFrame, area: Rect) {
+        let title = match self.mode {
+            PromptMode::Search => "search".to_owned(),
+            PromptMode::Filter => format!("filter: {}", self.kind),
+        };
+        let mut line = vec![Span::raw(self.input.as_str())];
+        if let Some(e) = &self.error {
+            line.push(Span::styled(format!("  {e}"), theme::fg(Slot::Error)));
+        }
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(Spans::from(line)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            ),
+            area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str) -> ListFilter {
+        ListFilter::new(FilterKind::Glob, pattern).unwrap()
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert_eq!(glob_to_regex("*.rs"), r"^.*\.rs$");
+        assert!(glob("*.rs").matches("main.rs"));
+        assert!(!glob("*.rs").matches("main.rs.orig"));
+        assert!(glob("a?c").matches("abc"));
+        assert!(!glob("a?c").matches("ac"));
+    }
+
+    #[test]
+    fn glob_sets() {
+        assert!(glob("file[0-9].txt").matches("file7.txt"));
+        assert!(!glob("file[0-9].txt").matches("filex.txt"));
+        assert!(glob("[!.]*").matches("src"));
+        assert!(!glob("[!.]*").matches(".svn"));
+        // characters special to a regex set are taken literally
+        assert!(glob(r"[\&]").matches("&"));
+        assert!(glob(r"[\&]").matches(r"\"));
+    }
+
+    #[test]
+    fn glob_escapes_the_rest() {
+        assert_eq!(glob_to_regex("a[b"), r"^a\[b$");
+        assert!(glob("a[b").matches("a[b"));
+        assert!(glob("(x)+{y}").matches("(x)+{y}"));
+        assert!(!glob("a.b").matches("axb"));
+    }
+
+    #[test]
+    fn substring_ignores_case() {
+        let filter = ListFilter::new(FilterKind::Substring, "READ.me").unwrap();
+        assert!(filter.matches("docs/Read.Me"));
+        assert!(!filter.matches("readXme"));
+    }
+}
@@ -0,0 +1,424 @@
+use crate::{
+    diff::DiffRange,
+    keymap::Action,
+    theme::{self, Slot},
+};
+use std::{collections::BTreeSet, path::Path};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+/// groups of the status view, in display order
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) enum ItemState {
+    Conflicted,
+    Modified,
+    Added,
+    Deleted,
+    Missing,
+    Unversioned,
+    /// unchanged locally, but changed in the repository
+    Incoming,
+    Other,
+}
+
+impl ItemState {
+    fn label(&self) -> &'static str {
+        match self {
+            ItemState::Conflicted => "conflicted",
+            ItemState::Modified => "modified",
+            ItemState::Added => "added",
+            ItemState::Deleted => "deleted",
+            ItemState::Missing => "missing",
+            ItemState::Unversioned => "unversioned",
+            ItemState::Incoming => "incoming",
+            ItemState::Other => "other",
+        }
+    }
+
+    /// states `svn commit` can send to the repository; a conflict has to be resolved first
+    fn committable(&self) -> bool {
+        matches!(
+            self,
+            ItemState::Modified | ItemState::Added | ItemState::Deleted
+        )
+    }
+
+    fn slot(&self) -> Slot {
+        match self {
+            ItemState::Conflicted => Slot::Conflicted,
+            ItemState::Modified => Slot::Modified,
+            ItemState::Added => Slot::Added,
+            ItemState::Deleted | ItemState::Missing => Slot::Deleted,
+            ItemState::Unversioned => Slot::Unversioned,
+            ItemState::Incoming => Slot::Incoming,
+            ItemState::Other => Slot::Muted,
+        }
+    }
+}
+
+/// one line of `svn status`
+#[derive(Clone, Debug)]
+pub(crate) struct StatusEntry {
+    pub(crate) state: ItemState,
+    /// the first seven columns, as printed by svn
+    flags: String,
+    pub(crate) path: String,
+    /// `-u` only: a newer revision exists in the repository
+    out_of_date: bool,
+}
+
+/// parses the plain text output of `svn status`, with or without `-u`
+pub(crate) fn parse(text: &str, incoming: bool) -> Vec<StatusEntry> {
+    let mut entries: Vec<StatusEntry> = text
+        .lines()
+        .filter_map(|line| parse_line(line, incoming))
+        .collect();
+    entries.sort_by(|a, b| a.state.cmp(&b.state).then_with(|| a.path.cmp(&b.path)));
+    entries
+}
+
+fn parse_line(line: &str, incoming: bool) -> Option<StatusEntry> {
+    let flags = line.get(..7)?;
+    // summary, changelist and tree-conflict description lines
+    if line.starts_with("Status against revision")
+        || line.starts_with("Performing status")
+        || line.starts_with("---")
+        || line.trim_start().starts_with('>')
+    {
+        return None;
+    }
+    let mut cols = flags.chars();
+    let item = cols.next()?;
+    let props = cols.next()?;
+    let tree_conflict = flags.chars().nth(6) == Some('C');
+    let (out_of_date, path) = if incoming {
+        // the working revision, right-aligned in 8 columns or more, then 3 spaces and the path.
+        // unversioned items and incoming additions have no working revision
+        let rest = line.get(10..)?;
+        let revision_end = match rest.find(|c| c != ' ') {
+            Some(start) if start < 8 => rest[start..].find(' ').map_or(rest.len(), |n| start + n),
+            _ => 8,
+        };
+        (line.get(8..9) == Some("*"), rest.get(revision_end + 3..)?)
+    } else {
+        (false, line.get(8..)?)
+    };
+    if path.is_empty() {
+        return None;
+    }
+    let state = match item {
+        _ if item == 'C' || props == 'C' || tree_conflict => ItemState::Conflicted,
+        'M' | 'R' => ItemState::Modified,
+        ' ' if props == 'M' => ItemState::Modified,
+        'A' => ItemState::Added,
+        'D' => ItemState::Deleted,
+        '!' => ItemState::Missing,
+        '?' => ItemState::Unversioned,
+        ' ' if out_of_date => ItemState::Incoming,
+        _ => ItemState::Other,
+    };
+    Some(StatusEntry {
+        state,
+        flags: flags.to_owned(),
+        path: path.to_owned(),
+        out_of_date,
+    })
+}
+
+pub(crate) enum StatusOutcome {
+    Browsing,
+    Close,
+    /// run `svn status` again, with `-u` if true
+    Refresh(bool),
+    Diff(String, DiffRange),
+    /// the selected item can't be acted on, with the reason why
+    Message(String),
+    /// open the commit dialog for the marked paths
+    Commit(Vec<String>),
+}
+
+/// `svn status` of a working copy, grouped by item state
+pub(crate) struct StatusView {
+    wc: String,
+    entries: Vec<StatusEntry>,
+    selected: usize,
+    /// paths marked for the next commit
+    marked: BTreeSet<String>,
+    incoming: bool,
+    loading: bool,
+}
+
+impl StatusView {
+    pub(crate) fn new(wc: String) -> Self {
+        Self {
+            wc,
+            entries: vec![],
+            selected: 0,
+            marked: BTreeSet::new(),
+            incoming: false,
+            loading: true,
+        }
+    }
+
+    pub(crate) fn wc(&self) -> &str {
+        &self.wc
+    }
+
+    pub(crate) fn incoming(&self) -> bool {
+        self.incoming
+    }
+
+    /// refreshing drops the marks of paths that were committed or reverted meanwhile
+    pub(crate) fn set_entries(&mut self, entries: Vec<StatusEntry>, incoming: bool) {
+        self.marked.retain(|p| {
+            entries
+                .iter()
+                .any(|e| &e.path == p && e.state.committable())
+        });
+        self.entries = entries;
+        self.incoming = incoming;
+        self.loading = false;
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    pub(crate) fn selected(&self) -> Option<&StatusEntry> {
+        self.entries.get(self.selected)
+    }
+
+    /// those of `paths` that are deleted directories with entries below them, which svn refuses
+    /// to commit non-recursively
+    pub(crate) fn deleted_directories(&self, paths: &[String]) -> Vec<String> {
+        let deleted = |path: &String| {
+            self.entries
+                .iter()
+                .any(|e| &e.path == path && e.state == ItemState::Deleted)
+        };
+        paths
+            .iter()
+            .filter(|p| deleted(p))
+            .filter(|p| {
+                self.entries
+                    .iter()
+                    .any(|e| &e.path != *p && Path::new(&e.path).starts_with(p))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn handle(&mut self, action: Action) -> StatusOutcome {
+        match action {
+            Action::Cancel | Action::Back => return StatusOutcome::Close,
+            Action::Down => {
+                self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1))
+            }
+            Action::Up => self.selected = self.selected.saturating_sub(1),
+            Action::Top => self.selected = 0,
+            Action::Bottom => self.selected = self.entries.len().saturating_sub(1),
+            Action::ToggleIncoming => {
+                self.loading = true;
+                return StatusOutcome::Refresh(!self.incoming);
+            }
+            Action::Refresh => {
+                self.loading = true;
+                return StatusOutcome::Refresh(self.incoming);
+            }
+            Action::Diff | Action::Open => {
+                if let Some(entry) = self.selected() {
+                    let path = entry.path.clone();
+                    return match entry.state {
+                        ItemState::Modified | ItemState::Conflicted | ItemState::Deleted => {
+                            StatusOutcome::Diff(path, DiffRange::Base)
+                        }
+                        _ if entry.out_of_date => StatusOutcome::Diff(path, DiffRange::Incoming),
+                        state => StatusOutcome::Message(format!(
+                            "'{path}' is {}, there is nothing to diff against BASE",
+                            state.label()
+                        )),
+                    };
+                }
+            }
+            Action::Mark => {
+                if let Some(entry) = self.selected() {
+                    if !entry.state.committable() {
+                        return StatusOutcome::Message(format!(
+                            "'{}' is {}, it can't be committed",
+                            entry.path,
+                            entry.state.label()
+                        ));
+                    }
+                    let path = entry.path.clone();
+                    if !self.marked.remove(&path) {
+                        self.marked.insert(path);
+                    }
+                    self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1));
+                }
+            }
+            Action::Commit => {
+                return if self.marked.is_empty() {
+                    StatusOutcome::Message("mark the paths to commit with space first".to_owned())
+                } else {
+                    StatusOutcome::Commit(self.marked.iter().cloned().collect())
+                };
+            }
+            _ => {}
+        }
+        StatusOutcome::Browsing
+    }
+
+    pub(crate) fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let mut items = vec![];
+        let mut selected_row = None;
+        let mut group = None;
+        for (n, entry) in self.entries.iter().enumerate() {
+            if group != Some(entry.state) {
+                group = Some(entry.state);
+                items.push(ListItem::new(Spans::from(Span::styled(
+                    format!("{}:", entry.state.label()),
+                    theme::fg(entry.state.slot())
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                ))));
+            }
+            if n == self.selected {
+                selected_row = Some(items.len());
+            }
+            let mark = if self.marked.contains(&entry.path) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let mut spans = vec![
+                Span::raw(format!("  {mark}")),
+                Span::styled(format!("  {} ", entry.flags), theme::fg(entry.state.slot())),
+                Span::raw(entry.path.clone()),
+            ];
+            if entry.out_of_date {
+                spans.push(Span::styled(" *", theme::fg(Slot::Incoming)));
+            }
+            items.push(ListItem::new(Spans::from(spans)));
+        }
+        let mut title = format!("status: {}", self.wc);
+        if self.incoming {
+            title.push_str(" (with incoming)");
+        }
+        if !self.marked.is_empty() {
+            title.push_str(&format!(" ({} marked)", self.marked.len()));
+        }
+        if self.loading {
+            title.push_str(" (loading...)");
+        } else if self.entries.is_empty() {
+            title.push_str(" (no changes)");
+        }
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(theme::fg(Slot::BorderAccent))
+                    .border_type(BorderType::Thick),
+            )
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .bg(theme::color(Slot::SelectedBg)),
+            )
+            .highlight_symbol(">>");
+        let mut state = ListState::default();
+        state.select(selected_row);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states(entries: &[StatusEntry]) -> Vec<(ItemState, &str, bool)> {
+        entries
+            .iter()
+            .map(|e| (e.state, e.path.as_str(), e.out_of_date))
+            .collect()
+    }
+
+    #[test]
+    fn parses_local_status() {
+        let text = "\
+M       src/main.rs
+?       notes with spaces.txt
+A  +    src/new.rs
+D       old
+!       gone.c
+ M      props_only
+      C src/tree_conflict.rs
+      >   local edit, incoming delete upon update
+";
+        let entries = parse(text, false);
+        assert_eq!(
+            states(&entries),
+            vec![
+                (ItemState::Conflicted, "src/tree_conflict.rs", false),
+                (ItemState::Modified, "props_only", false),
+                (ItemState::Modified, "src/main.rs", false),
+                (ItemState::Added, "src/new.rs", false),
+                (ItemState::Deleted, "old", false),
+                (ItemState::Missing, "gone.c", false),
+                (ItemState::Unversioned, "notes with spaces.txt", false),
+            ]
+        );
+        assert_eq!(entries[2].flags, "M      ");
+    }
+
+    #[test]
+    fn parses_status_with_incoming_changes() {
+        let text = "\
+M              965   src/main.rs
+        *      965   src/lib.rs
+        *            newfile
+?                    notes with spaces.txt
+A                -   added.rs
+M       * 1234567890   huge revision.rs
+Status against revision:    981
+";
+        assert_eq!(
+            states(&parse(text, true)),
+            vec![
+                (ItemState::Modified, "huge revision.rs", true),
+                (ItemState::Modified, "src/main.rs", false),
+                (ItemState::Added, "added.rs", false),
+                (ItemState::Unversioned, "notes with spaces.txt", false),
+                (ItemState::Incoming, "newfile", true),
+                (ItemState::Incoming, "src/lib.rs", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_numeric_name_is_not_a_revision() {
+        let entries = parse("?                    2024\n", true);
+        assert_eq!(
+            states(&entries),
+            vec![(ItemState::Unversioned, "2024", false)]
+        );
+    }
+    #[test]
+    fn only_deleted_directories_with_children_go_recursive() {
+        let text = "\
+D       gone
+D       gone/a.txt
+D       empty
+M       kept
+M       kept/b.txt
+C       conflicted.txt
+";
+        let mut view = StatusView::new(".".to_owned());
+        view.set_entries(parse(text, false), false);
+        let marked = ["gone", "empty", "kept"].map(str::to_owned);
+        assert_eq!(view.deleted_directories(&marked), ["gone"]);
+        assert!(!ItemState::Conflicted.committable());
+    }
+}
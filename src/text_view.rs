@@ -0,0 +1,45 @@
+use crate::{blame::BlameView, diff::Diff};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::Spans,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+/// what the text pane on the right is showing
+pub(crate) enum TextContent {
+    Plain(Vec<Spans<'static>>),
+    Diff(Diff),
+    Blame(BlameView),
+}
+
+impl TextContent {
+    pub(crate) fn rows(&self) -> usize {
+        match self {
+            TextContent::Plain(text) => text.len(),
+            TextContent::Diff(diff) => diff.rows(),
+            TextContent::Blame(blame) => blame.rows(),
+        }
+    }
+
+    pub(crate) fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        block: Block,
+        scroll: usize,
+    ) {
+        match self {
+            // only the rows on screen; a u16 scroll offset wouldn't reach far into a long file
+            TextContent::Plain(text) => {
+                let height = usize::from(block.inner(area).height);
+                let rows = text.iter().skip(scroll).take(height).cloned();
+                frame.render_widget(Paragraph::new(rows.collect::<Vec<_>>()).block(block), area)
+            }
+            TextContent::Diff(diff) => diff.render(frame, area, block, scroll),
+            // blame keeps its own scroll position, following the line cursor
+            TextContent::Blame(blame) => blame.render(frame, area, block),
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use crate::{config, CustomError};
+use log::debug;
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, sync::OnceLock};
+use tui::style::{Color, Style};
+
+const THEME_FILE: &str = "theme.toml";
+
+/// the default palette, light colours on a dark terminal background
+const DARK: &str = r##"[colors]
+message = "light-magenta"
+info = "light-cyan"
+warning = "light-yellow"
+error = "light-red"
+ok = "light-green"
+muted = "gray"
+faint = "dark-gray"
+accent = "light-yellow"
+border = "reset"
+border_accent = "light-cyan"
+border_focused = "light-yellow"
+border_text = "light-magenta"
+border_alert = "light-red"
+directory = "light-blue"
+file = "blue"
+selected = "light-yellow"
+selected_bg = "dark-gray"
+diff_file = "light-cyan"
+diff_hunk = "light-magenta"
+diff_add = "light-green"
+diff_remove = "light-red"
+diff_add_word = "green"
+diff_remove_word = "red"
+diff_word_text = "white"
+added = "light-green"
+modified = "light-yellow"
+deleted = "light-magenta"
+conflicted = "light-red"
+replaced = "light-cyan"
+incoming = "light-cyan"
+unversioned = "gray"
+blame_age_1 = "22"
+blame_age_2 = "23"
+blame_age_3 = "17"
+blame_age_4 = "237"
+blame_age_5 = "235"
+"##;
+
+/// dark text on a light background; no yellow or cyan, which fade on white
+const LIGHT: &str = r##"[colors]
+message = "magenta"
+info = "blue"
+warning = "130"
+error = "red"
+ok = "28"
+muted = "dark-gray"
+faint = "gray"
+accent = "blue"
+border = "reset"
+border_accent = "blue"
+border_focused = "166"
+border_text = "dark-gray"
+border_alert = "red"
+directory = "blue"
+file = "black"
+selected = "166"
+selected_bg = "254"
+diff_file = "blue"
+diff_hunk = "magenta"
+diff_add = "28"
+diff_remove = "124"
+diff_add_word = "194"
+diff_remove_word = "224"
+diff_word_text = "black"
+added = "28"
+modified = "130"
+deleted = "magenta"
+conflicted = "red"
+replaced = "blue"
+incoming = "blue"
+unversioned = "dark-gray"
+blame_age_1 = "194"
+blame_age_2 = "195"
+blame_age_3 = "189"
+blame_age_4 = "254"
+blame_age_5 = "255"
+"##;
+
+/// bright colours only, on the terminal's own background
+const HIGH_CONTRAST: &str = r##"[colors]
+message = "white"
+info = "light-cyan"
+warning = "light-yellow"
+error = "light-red"
+ok = "light-green"
+muted = "white"
+faint = "gray"
+accent = "light-yellow"
+border = "white"
+border_accent = "white"
+border_focused = "light-yellow"
+border_text = "white"
+border_alert = "light-red"
+directory = "light-cyan"
+file = "white"
+selected = "light-yellow"
+selected_bg = "blue"
+diff_file = "light-cyan"
+diff_hunk = "light-magenta"
+diff_add = "light-green"
+diff_remove = "light-red"
+diff_add_word = "green"
+diff_remove_word = "red"
+diff_word_text = "white"
+added = "light-green"
+modified = "light-yellow"
+deleted = "light-magenta"
+conflicted = "light-red"
+replaced = "light-cyan"
+incoming = "light-cyan"
+unversioned = "white"
+blame_age_1 = "reset"
+blame_age_2 = "reset"
+blame_age_3 = "reset"
+blame_age_4 = "reset"
+blame_age_5 = "reset"
+"##;
+
+/// the 16 colours every terminal has, with their usual xterm values
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// what a colour is used for; a theme file names them in snake_case
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Slot {
+    Message,
+    Info,
+    Warning,
+    Error,
+    Ok,
+    Muted,
+    Faint,
+    /// revision headers and other things to spot quickly
+    Accent,
+    Border,
+    /// lists and popups
+    BorderAccent,
+    BorderFocused,
+    /// the text pane
+    BorderText,
+    BorderAlert,
+    Directory,
+    File,
+    Selected,
+    /// background of the selected row in popups and full views
+    SelectedBg,
+    DiffFile,
+    DiffHunk,
+    DiffAdd,
+    DiffRemove,
+    /// background of the words that changed, in the side-by-side diff
+    DiffAddWord,
+    DiffRemoveWord,
+    DiffWordText,
+    Added,
+    Modified,
+    Deleted,
+    Conflicted,
+    Replaced,
+    Incoming,
+    Unversioned,
+    /// backgrounds of blame lines, from the newest to the oldest
+    #[serde(rename = "blame_age_1")]
+    BlameAge1,
+    #[serde(rename = "blame_age_2")]
+    BlameAge2,
+    #[serde(rename = "blame_age_3")]
+    BlameAge3,
+    #[serde(rename = "blame_age_4")]
+    BlameAge4,
+    #[serde(rename = "blame_age_5")]
+    BlameAge5,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+#[derive(Deserialize)]
+struct ThemeFile {
+    colors: HashMap<Slot, String>,
+}
+
+/// colours by slot; slots left out are drawn in the terminal's default colours
+#[derive(Default)]
+pub(crate) struct Theme {
+    colors: HashMap<Slot, Color>,
+}
+
+/// a colour name like "light-cyan", "#rrggbb", or a number of the 256-colour palette
+fn parse_color(name: &str) -> Result<Color, CustomError> {
+    let invalid = || CustomError::InvalidColor(name.to_owned());
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(invalid)
+        };
+        if hex.len() != 6 {
+            return Err(invalid());
+        }
+        return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(n) = name.parse::<u8>() {
+        return Ok(Color::Indexed(n));
+    }
+    let plain: String = name
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let color = match plain.as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(invalid()),
+    };
+    Ok(color)
+}
+
+/// the closest of the 16 basic colours
+fn to_ansi16(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(n) if n < 16 => return ANSI16[n as usize].0,
+        // the 6x6x6 cube, then the grey ramp
+        Color::Indexed(n @ 16..=231) => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+            let n = n - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        Color::Indexed(n) => {
+            let v = 8 + 10 * (n - 232);
+            (v, v, v)
+        }
+        color => return color,
+    };
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        [(r, cr), (g, cg), (b, cb)]
+            .iter()
+            .map(|&(a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    ANSI16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or(color, |(c, _)| *c)
+}
+
+/// true if the terminal is likely to show more than 16 colours
+fn rich_terminal() -> bool {
+    env::var("COLORTERM").is_ok_and(|c| !c.is_empty())
+        || env::var("TERM").is_ok_and(|t| t.contains("256color"))
+}
+
+impl Theme {
+    fn from_toml(text: &str) -> Result<HashMap<Slot, Color>, CustomError> {
+        let ThemeFile { colors } = toml::from_str(text)?;
+        colors
+            .into_iter()
+            .map(|(slot, name)| Ok((slot, parse_color(&name)?)))
+            .collect()
+    }
+
+    /// the built-in theme, with the colours of the theme file on top
+    pub(crate) fn load(config: &config::ThemeConfig) -> Result<Self, CustomError> {
+        let builtin = match config.name {
+            ThemeName::Dark => DARK,
+            ThemeName::Light => LIGHT,
+            ThemeName::HighContrast => HIGH_CONTRAST,
+        };
+        let mut colors = Self::from_toml(builtin)?;
+        let file = config.file.clone().or_else(|| {
+            config::config_dir()
+                .map(|d| d.join(THEME_FILE))
+                .filter(|p| p.exists())
+        });
+        if let Some(path) = file {
+            debug!("loading theme from {path:?}");
+            colors.extend(Self::from_toml(&fs::read_to_string(&path)?)?);
+        }
+        if config.colors.map_or(!rich_terminal(), |c| c <= 16) {
+            for color in colors.values_mut() {
+                *color = to_ansi16(*color);
+            }
+        }
+        Ok(Self { colors })
+    }
+}
+
+/// makes `theme` the one everything is drawn with; only the first call counts
+pub(crate) fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+// styles are also built away from drawing, like the side-by-side diff when a cached diff is
+// loaded, so the theme is global rather than handed to every render call
+fn current() -> &'static Theme {
+    THEME.get_or_init(|| Theme {
+        colors: Theme::from_toml(DARK).unwrap_or_default(),
+    })
+}
+
+pub(crate) fn color(slot: Slot) -> Color {
+    current().colors.get(&slot).copied().unwrap_or(Color::Reset)
+}
+
+pub(crate) fn fg(slot: Slot) -> Style {
+    Style::default().fg(color(slot))
+}
@@ -1,9 +1,450 @@
-use crossterm::event::Event;
+use crate::{
+    config::LayoutConfig,
+    data_handler::{DataHandler, DataRequest, DataResponse, ViewId},
+    errors::ErrorLog,
+    keymap::Action,
+    lister::{CustomList, CustomListState, CustomLists, CustomListsToDisplay, ListEntry, ListOps},
+    log_browser::LogBrowser,
+};
+use crossterm::event::KeyEvent;
+use serde::Deserialize;
+use std::{
+    io::Stdout,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    Frame,
+};
 
-pub trait Screenlet {
-    type Data;
+pub(crate) type UiFrame<'a> = Frame<'a, CrosstermBackend<Stdout>>;
 
-    fn read(&self) -> Self::Data;
-    fn event(&mut self, e: Event);
-    fn update(&mut self);
+/// the panes of the main screen; popups and full views are drawn over them
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PaneId {
+    Messages,
+    /// the list two levels up
+    #[serde(rename = "grandparent")]
+    Pprev,
+    /// the list one level up
+    #[serde(rename = "parent")]
+    Prev,
+    List,
+    Info,
+    CommitMessage,
+    Text,
+}
+
+/// state the panes share; everything else belongs to the pane that shows it
+pub(crate) struct Context {
+    pub(crate) data_handler: Arc<DataHandler>,
+    pub(crate) message: String,
+    pub(crate) error_log: ErrorLog,
+    pub(crate) lists: CustomLists,
+    pub(crate) list_state: CustomListState,
+    /// sent once the current event is handled
+    pub(crate) new_data_request: Option<(DataRequest, ViewId)>,
+    /// set by a pane that wants the log browser opened, e.g. the blame view for a revision
+    pub(crate) open_log_browser: Option<LogBrowser>,
+    /// set by a pane that wants the focus, e.g. the text pane once it shows a blame
+    pub(crate) focus: Option<PaneId>,
+    /// advances the spinner of the messages pane
+    pub(crate) tick: usize,
+}
+
+impl Context {
+    /// url of the current list and its selected entry
+    pub(crate) fn selected(&self) -> Option<(String, ListEntry)> {
+        match self.lists.get_current() {
+            CustomListsToDisplay {
+                cur: Some(list), ..
+            } => {
+                let selected = list.get_current_selected(&self.list_state)?;
+                Some((list.base_url, selected))
+            }
+            _ => None,
+        }
+    }
+
+    /// shows the lists as if they had been entered one after the other, and selects an entry of
+    /// the last; the first one replaces the current list if it is the same directory
+    pub(crate) fn go_to(&mut self, lists: Vec<CustomList>, selected: usize) {
+        for list in lists {
+            let same = self
+                .lists
+                .get_current()
+                .cur
+                .is_some_and(|cur| cur.base_url == list.base_url);
+            if same {
+                self.lists.replace_current(list);
+            } else {
+                self.lists.add_new_list(list);
+            }
+        }
+        if let Some(list) = self.lists.get_current().cur {
+            self.list_state = CustomListState::from(list);
+            self.list_state.select(Some(selected));
+        }
+    }
+}
+
+/// one pane of the main screen, with its own state, keys and drawing
+pub(crate) trait Screenlet {
+    fn id(&self) -> PaneId;
+
+    /// responses to requests made for this view are handed to the pane
+    fn view_id(&self) -> Option<ViewId> {
+        None
+    }
+
+    /// panes without keys of their own are skipped when moving the focus
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    /// true while the pane wants every key, before the focused one
+    fn captures(&self) -> bool {
+        false
+    }
+
+    /// true while the pane's actions only mean something to it when it has the focus; it isn't
+    /// offered the actions the focused pane passes on
+    fn only_focused(&self) -> bool {
+        false
+    }
+
+    /// false if the action isn't meant for this pane
+    fn event(&mut self, action: Action, ctx: &mut Context) -> bool;
+
+    /// a key for a text field of the pane, before the keymap sees it; only asked while capturing
+    fn key(&mut self, _key: KeyEvent, _ctx: &mut Context) -> bool {
+        false
+    }
+
+    fn response(&mut self, _req: DataRequest, _response: DataResponse, _ctx: &mut Context) {}
+
+    /// catches up with the shared state, after every event
+    fn update(&mut self, _ctx: &mut Context) {}
+
+    fn render(&mut self, frame: &mut UiFrame, area: Rect, focused: bool, ctx: &mut Context);
+}
+
+/// routes actions and responses to the panes, moves the focus between them and lays them out
+pub(crate) struct FocusManager {
+    panes: Vec<Box<dyn Screenlet>>,
+    focused: usize,
+    layout: LayoutConfig,
+    /// panes that got an area on the last draw; the focus only moves between these
+    shown: Vec<PaneId>,
+    /// the focused pane takes the whole screen
+    zoomed: bool,
+}
+
+impl FocusManager {
+    pub(crate) fn new(
+        panes: Vec<Box<dyn Screenlet>>,
+        focused: PaneId,
+        layout: LayoutConfig,
+    ) -> Self {
+        let focused = panes.iter().position(|p| p.id() == focused).unwrap_or(0);
+        let shown = panes.iter().map(|p| p.id()).collect();
+        Self {
+            panes,
+            focused,
+            layout,
+            shown,
+            zoomed: false,
+        }
+    }
+
+    /// a capturing pane gets the action first, then the focused one, then the others in order
+    pub(crate) fn route(&mut self, action: Action, ctx: &mut Context) -> bool {
+        match action {
+            Action::FocusNext => return self.cycle(true),
+            Action::FocusPrev => return self.cycle(false),
+            Action::FocusList => return self.focus(PaneId::List),
+            Action::FocusText => return self.focus(PaneId::Text),
+            Action::FocusCommitMessage => return self.focus(PaneId::CommitMessage),
+            Action::Zoom => {
+                self.zoomed = !self.zoomed;
+                return true;
+            }
+            _ => {}
+        }
+        let capturing = self.panes.iter().position(|p| p.captures());
+        let first = capturing.unwrap_or(self.focused);
+        if self.panes[first].event(action, ctx) {
+            return true;
+        }
+        if capturing.is_some() {
+            return false;
+        }
+        self.panes
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, pane)| *i != first && !pane.only_focused())
+            .any(|(_, pane)| pane.event(action, ctx))
+    }
+
+    /// a key typed into the capturing pane; false if it doesn't take keys as they are
+    pub(crate) fn key(&mut self, key: KeyEvent, ctx: &mut Context) -> bool {
+        self.panes
+            .iter_mut()
+            .find(|p| p.captures())
+            .is_some_and(|pane| pane.key(key, ctx))
+    }
+
+    fn can_focus(&self, i: usize) -> bool {
+        self.panes[i].focusable() && self.shown.contains(&self.panes[i].id())
+    }
+
+    fn focus(&mut self, id: PaneId) -> bool {
+        match (0..self.panes.len()).find(|i| self.panes[*i].id() == id && self.can_focus(*i)) {
+            Some(i) => {
+                self.focused = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// moves the focus to the next focusable pane, or the previous one
+    fn cycle(&mut self, forward: bool) -> bool {
+        let focusable: Vec<usize> = (0..self.panes.len())
+            .filter(|i| self.can_focus(*i))
+            .collect();
+        let Some(pos) = focusable.iter().position(|i| *i == self.focused) else {
+            return false;
+        };
+        let len = focusable.len();
+        let next = if forward { pos + 1 } else { pos + len - 1 };
+        self.focused = focusable[next % len];
+        true
+    }
+
+    /// hands an action to one pane, whether focused or not
+    pub(crate) fn send_to(&mut self, id: PaneId, action: Action, ctx: &mut Context) -> bool {
+        self.panes
+            .iter_mut()
+            .find(|p| p.id() == id)
+            .is_some_and(|pane| pane.event(action, ctx))
+    }
+
+    /// false if no pane shows responses of this view
+    pub(crate) fn respond(
+        &mut self,
+        view_id: ViewId,
+        req: DataRequest,
+        response: DataResponse,
+        ctx: &mut Context,
+    ) -> bool {
+        match self.panes.iter_mut().find(|p| p.view_id() == Some(view_id)) {
+            Some(pane) => {
+                pane.response(req, response, ctx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn update(&mut self, ctx: &mut Context) {
+        for pane in &mut self.panes {
+            pane.update(ctx);
+        }
+        if let Some(id) = ctx.focus.take() {
+            self.focus(id);
+        }
+    }
+
+    /// where each pane goes on a screen of this size; only the focused one while zoomed
+    pub(crate) fn layout(&mut self, area: Rect) -> Vec<(PaneId, Rect)> {
+        let areas = layout(area, &self.layout);
+        self.shown = areas.iter().map(|(id, _)| *id).collect();
+        // a pane that went away, like the parent lists on a narrow screen, can't keep the focus
+        if !self.can_focus(self.focused) {
+            if let Some(i) = (0..self.panes.len()).find(|i| self.can_focus(*i)) {
+                self.focused = i;
+            }
+        }
+        if self.zoomed {
+            return vec![(self.panes[self.focused].id(), area)];
+        }
+        areas
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut UiFrame,
+        areas: &[(PaneId, Rect)],
+        ctx: &mut Context,
+    ) {
+        for (i, pane) in self.panes.iter_mut().enumerate() {
+            if let Some((_, area)) = areas.iter().find(|(id, _)| *id == pane.id()) {
+                pane.render(frame, *area, i == self.focused, ctx);
+            }
+        }
+    }
+}
+
+/// where each pane goes on a screen of this size; hidden panes leave their space to the others
+fn layout(area: Rect, config: &LayoutConfig) -> Vec<(PaneId, Rect)> {
+    let [grandparent, parent, list, text] = config.columns;
+    let [info, commit_message] = config.bottom;
+    let rows = [
+        (config.rows[0], vec![(PaneId::Messages, 1)]),
+        (
+            config.rows[1],
+            vec![
+                (PaneId::Pprev, grandparent),
+                (PaneId::Prev, parent),
+                (PaneId::List, list),
+                (PaneId::Text, text),
+            ],
+        ),
+        (
+            config.rows[2],
+            vec![
+                (PaneId::Info, info),
+                (PaneId::CommitMessage, commit_message),
+            ],
+        ),
+    ];
+    let single_column = area.width < config.single_column_width;
+    let shown = |id: &PaneId| match id {
+        // one column has no room for the parent lists
+        PaneId::Pprev | PaneId::Prev if single_column => false,
+        id => !config.hidden.contains(id),
+    };
+    let rows: Vec<(u32, Vec<(PaneId, u32)>)> = rows
+        .into_iter()
+        .map(|(height, panes)| {
+            let panes = panes
+                .into_iter()
+                .filter(|(id, width)| *width > 0 && shown(id))
+                .map(|(id, width)| (id, u32::from(width)))
+                .collect();
+            (u32::from(height), panes)
+        })
+        .filter(|(height, panes): &(u32, Vec<_>)| *height > 0 && !panes.is_empty())
+        .collect();
+    if single_column {
+        // the panes of a row share its height in proportion to their widths; in u64, the sizes
+        // come from the config and may be anything up to u16::MAX
+        let panes: Vec<(PaneId, u32)> = rows
+            .iter()
+            .flat_map(|(height, panes)| {
+                let total: u64 = panes.iter().map(|(_, width)| u64::from(*width)).sum();
+                panes.iter().map(move |(id, width)| {
+                    let share = u64::from(*height) * u64::from(*width) * 1000 / total;
+                    // at most height * 1000
+                    (*id, u32::try_from(share).unwrap_or(u32::MAX))
+                })
+            })
+            .collect();
+        let heights: Vec<u32> = panes.iter().map(|(_, height)| *height).collect();
+        return panes
+            .iter()
+            .map(|(id, _)| *id)
+            .zip(split(area, Direction::Vertical, &heights))
+            .collect();
+    }
+    let heights: Vec<u32> = rows.iter().map(|(height, _)| *height).collect();
+    split(area, Direction::Vertical, &heights)
+        .into_iter()
+        .zip(&rows)
+        .flat_map(|(row, (_, panes))| {
+            let widths: Vec<u32> = panes.iter().map(|(_, width)| *width).collect();
+            panes
+                .iter()
+                .map(|(id, _)| *id)
+                .zip(split(row, Direction::Horizontal, &widths))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// splits `area` into parts of the given sizes, relative to each other
+fn split(area: Rect, direction: Direction, sizes: &[u32]) -> Vec<Rect> {
+    let total = sizes.iter().sum::<u32>().max(1);
+    Layout::default()
+        .direction(direction)
+        .constraints(
+            sizes
+                .iter()
+                .map(|size| Constraint::Ratio(*size, total))
+                .collect::<Vec<_>>(),
+        )
+        .split(area)
+}
+
+/// a value acted on once it stopped changing for a while, like the selection while scrolling
+pub(crate) struct Settled<T> {
+    value: T,
+    since: Instant,
+    done: bool,
+}
+
+impl<T: PartialEq> Settled<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            value,
+            since: Instant::now(),
+            done: true,
+        }
+    }
+
+    pub(crate) fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// true if the value changed, which restarts the wait
+    pub(crate) fn set(&mut self, value: T) -> bool {
+        if value == self.value {
+            return false;
+        }
+        *self = Self {
+            value,
+            since: Instant::now(),
+            done: false,
+        };
+        true
+    }
+
+    /// the value, once, after it rested for `delay`
+    pub(crate) fn take(&mut self, delay: Duration) -> Option<&T> {
+        if self.done || self.since.elapsed() < delay {
+            return None;
+        }
+        self.done = true;
+        Some(&self.value)
+    }
+}
+
+/// rect of the given percentage size, centered inside `r`; used for popups
+pub(crate) fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
 }